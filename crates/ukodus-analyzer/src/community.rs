@@ -0,0 +1,156 @@
+//! Community detection over the `SHARES_TECHNIQUE_PROFILE` graph.
+//!
+//! Weighted label propagation: every puzzle starts in its own community and
+//! repeatedly adopts the label with the highest total edge similarity among
+//! its neighbours. Puzzles are visited in a fixed order and ties go to the
+//! smallest label, so the same graph always yields the same communities.
+
+use std::collections::{BTreeMap, HashMap};
+
+/// Assign a community to each of `n` points joined by weighted `edges`.
+///
+/// Returns one label per point; labels are compact (`0..k`) and numbered in
+/// order of each community's first point.
+pub fn label_propagation(
+    n: usize,
+    edges: &[(usize, usize, f64)],
+    max_iterations: usize,
+) -> Vec<usize> {
+    let mut adjacency: Vec<Vec<(usize, f64)>> = vec![Vec::new(); n];
+    for &(a, b, weight) in edges {
+        adjacency[a].push((b, weight));
+        adjacency[b].push((a, weight));
+    }
+
+    let mut labels: Vec<usize> = (0..n).collect();
+    for _ in 0..max_iterations {
+        let mut changed = false;
+        for i in 0..n {
+            if adjacency[i].is_empty() {
+                continue;
+            }
+            let mut weights: BTreeMap<usize, f64> = BTreeMap::new();
+            for &(j, weight) in &adjacency[i] {
+                *weights.entry(labels[j]).or_default() += weight;
+            }
+            // BTreeMap order plus a strict comparison keeps the smallest
+            // label on ties
+            let mut best = (labels[i], weights.get(&labels[i]).copied().unwrap_or(0.0));
+            for (&label, &weight) in &weights {
+                if weight > best.1 || (weight == best.1 && label < best.0) {
+                    best = (label, weight);
+                }
+            }
+            if best.0 != labels[i] {
+                labels[i] = best.0;
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+
+    let mut compact: HashMap<usize, usize> = HashMap::new();
+    labels
+        .into_iter()
+        .map(|label| {
+            let next = compact.len();
+            *compact.entry(label).or_insert(next)
+        })
+        .collect()
+}
+
+/// Profile of one community, as stored on its `Cluster` node.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommunitySummary {
+    pub size: usize,
+    pub min_se_rating: f64,
+    pub max_se_rating: f64,
+    /// The hardest techniques most often required by members, most common first
+    pub dominant_techniques: Vec<String>,
+}
+
+/// One member's rating and hardest technique.
+pub struct Member<'a> {
+    pub se_rating: f64,
+    pub max_technique: Option<&'a str>,
+}
+
+/// Summarize a community. Every puzzle needs singles, so dominance is
+/// measured on each member's hardest technique instead of its whole profile.
+pub fn summarize(members: &[Member], top: usize) -> CommunitySummary {
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for m in members {
+        if let Some(t) = m.max_technique {
+            *counts.entry(t).or_default() += 1;
+        }
+    }
+    let mut ranked: Vec<(&str, usize)> = counts.into_iter().collect();
+    // Stable sort keeps ties in name order
+    ranked.sort_by_key(|&(_, count)| std::cmp::Reverse(count));
+
+    let ratings = members.iter().map(|m| m.se_rating);
+    CommunitySummary {
+        size: members.len(),
+        min_se_rating: ratings.clone().fold(f64::INFINITY, f64::min),
+        max_se_rating: ratings.fold(f64::NEG_INFINITY, f64::max),
+        dominant_techniques: ranked
+            .into_iter()
+            .take(top)
+            .map(|(t, _)| t.to_string())
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_label_propagation_components_and_ties() {
+        let edges = vec![
+            // Two triangles
+            (0, 1, 1.0),
+            (1, 2, 1.0),
+            (0, 2, 1.0),
+            (3, 4, 1.0),
+            (4, 5, 1.0),
+            (3, 5, 1.0),
+            // 6 is pulled equally towards both and joins the smaller label
+            (6, 2, 0.5),
+            (6, 3, 0.5),
+            // 7 is isolated; 8-9 is a separate component
+            (8, 9, 1.0),
+        ];
+        let labels = label_propagation(10, &edges, 20);
+        assert_eq!(labels, vec![0, 0, 0, 1, 1, 1, 0, 2, 3, 3]);
+        assert_eq!(labels, label_propagation(10, &edges, 20));
+    }
+
+    #[test]
+    fn test_summarize() {
+        let members = [
+            Member {
+                se_rating: 2.0,
+                max_technique: Some("Naked Pair"),
+            },
+            Member {
+                se_rating: 4.2,
+                max_technique: Some("X-Wing"),
+            },
+            Member {
+                se_rating: 3.4,
+                max_technique: Some("X-Wing"),
+            },
+            Member {
+                se_rating: 1.5,
+                max_technique: None,
+            },
+        ];
+        let summary = summarize(&members, 3);
+        assert_eq!(summary.size, 4);
+        assert_eq!((summary.min_se_rating, summary.max_se_rating), (1.5, 4.2));
+        assert_eq!(summary.dominant_techniques, vec!["X-Wing", "Naked Pair"]);
+    }
+}
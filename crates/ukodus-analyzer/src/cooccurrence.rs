@@ -0,0 +1,88 @@
+//! Technique co-occurrence across puzzle profiles.
+//!
+//! Two techniques co-occur when one puzzle requires both. Raw counts favour
+//! techniques that appear everywhere, so each pair also gets its lift: how
+//! much more often the pair appears than it would if the techniques were
+//! independent (`P(a and b) / (P(a) * P(b))`). Lift above 1 means the
+//! techniques tend to show up together.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CoOccurrence {
+    /// The alphabetically smaller technique name
+    pub a: String,
+    pub b: String,
+    /// Puzzles requiring both techniques
+    pub count: u64,
+    pub lift: f64,
+}
+
+/// Count technique pairs over every profile (one list of technique names per
+/// puzzle), keeping pairs seen in at least `min_count` puzzles. Results are
+/// sorted by `(a, b)`.
+pub fn co_occurrence(profiles: &[Vec<String>], min_count: u64) -> Vec<CoOccurrence> {
+    let mut singles: BTreeMap<&str, u64> = BTreeMap::new();
+    let mut pairs: BTreeMap<(&str, &str), u64> = BTreeMap::new();
+    let mut total = 0u64;
+
+    for profile in profiles {
+        let techniques: BTreeSet<&str> = profile.iter().map(String::as_str).collect();
+        if techniques.is_empty() {
+            continue;
+        }
+        total += 1;
+        let techniques: Vec<&str> = techniques.into_iter().collect();
+        for (i, a) in techniques.iter().enumerate() {
+            *singles.entry(a).or_default() += 1;
+            for b in &techniques[i + 1..] {
+                *pairs.entry((a, b)).or_default() += 1;
+            }
+        }
+    }
+
+    pairs
+        .into_iter()
+        .filter(|&(_, count)| count >= min_count.max(1))
+        .map(|((a, b), count)| {
+            let expected = singles[a] as f64 * singles[b] as f64 / total as f64;
+            CoOccurrence {
+                a: a.to_string(),
+                b: b.to_string(),
+                count,
+                lift: count as f64 / expected,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(techniques: &[&str]) -> Vec<String> {
+        techniques.iter().map(|t| t.to_string()).collect()
+    }
+
+    #[test]
+    fn test_co_occurrence_counts_and_lift() {
+        let profiles = vec![
+            profile(&["Single", "XWing", "Swordfish"]),
+            profile(&["Single", "XWing", "Swordfish", "XWing"]),
+            profile(&["Single", "Pair"]),
+            profile(&["Single", "Pair"]),
+            profile(&[]),
+        ];
+        let pairs = co_occurrence(&profiles, 2);
+        let find = |a: &str, b: &str| pairs.iter().find(|p| p.a == a && p.b == b).cloned();
+
+        // 4 puzzles; Swordfish and XWing are both in 2 of them, always together
+        let fish = find("Swordfish", "XWing").unwrap();
+        assert_eq!(fish.count, 2);
+        assert!((fish.lift - 2.0).abs() < 1e-9);
+        // Singles are everywhere, so pairing with them carries no signal
+        assert!((find("Single", "XWing").unwrap().lift - 1.0).abs() < 1e-9);
+        assert!(find("Pair", "XWing").is_none());
+        assert_eq!(pairs.len(), 4);
+    }
+}
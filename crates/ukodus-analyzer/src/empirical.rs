@@ -0,0 +1,269 @@
+use std::collections::HashMap;
+
+use ukodus_grid::{tier_index, DIFFICULTY_TIERS};
+
+/// Bounds for a player's skill factor, so a handful of outlier games cannot
+/// scale someone's times by more than 4x either way.
+const MIN_SKILL: f64 = 0.25;
+const MAX_SKILL: f64 = 4.0;
+
+/// A verified game result used as input to the empirical model.
+#[derive(Debug, Clone)]
+pub struct ResultSample {
+    pub puzzle_hash: String,
+    pub player_id: String,
+    pub won: bool,
+    pub time_secs: f64,
+    pub mistakes: u32,
+}
+
+/// Observed difficulty of one puzzle, derived from real player results.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EmpiricalDifficulty {
+    /// Number of verified results the figures are based on
+    pub samples: usize,
+    /// Solve times are `None` when nobody has won the puzzle yet
+    pub p10_solve_time: Option<f64>,
+    pub median_solve_time: Option<f64>,
+    pub p90_solve_time: Option<f64>,
+    pub win_rate: f64,
+    /// Mean mistakes per game
+    pub mistake_rate: f64,
+    /// Median solve time after dividing out each winner's skill factor
+    pub normalized_solve_time: Option<f64>,
+}
+
+/// Linear-interpolated percentile of an ascending slice (`p` in 0.0..=1.0).
+pub fn percentile(sorted: &[f64], p: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let rank = p.clamp(0.0, 1.0) * (sorted.len() - 1) as f64;
+    let lo = rank.floor() as usize;
+    let hi = rank.ceil() as usize;
+    sorted[lo] + (sorted[hi] - sorted[lo]) * (rank - lo as f64)
+}
+
+fn sorted(mut values: Vec<f64>) -> Vec<f64> {
+    values.sort_by(|a, b| a.total_cmp(b));
+    values
+}
+
+/// Median winning solve time per puzzle.
+fn raw_medians(samples: &[ResultSample]) -> HashMap<&str, f64> {
+    let mut times: HashMap<&str, Vec<f64>> = HashMap::new();
+    for s in samples.iter().filter(|s| s.won && s.time_secs > 0.0) {
+        times
+            .entry(s.puzzle_hash.as_str())
+            .or_default()
+            .push(s.time_secs);
+    }
+    times
+        .into_iter()
+        .map(|(hash, t)| (hash, percentile(&sorted(t), 0.5)))
+        .collect()
+}
+
+/// Estimate each player's speed relative to the crowd.
+///
+/// The factor is the geometric mean of `time / puzzle median` over the
+/// player's wins: 0.5 means they usually finish in half the typical time.
+/// Players with fewer than `min_games` wins get a neutral 1.0.
+pub fn player_skill(samples: &[ResultSample], min_games: usize) -> HashMap<String, f64> {
+    let medians = raw_medians(samples);
+    let mut log_ratios: HashMap<&str, Vec<f64>> = HashMap::new();
+    for s in samples.iter().filter(|s| s.won && s.time_secs > 0.0) {
+        if let Some(median) = medians.get(s.puzzle_hash.as_str()) {
+            log_ratios
+                .entry(s.player_id.as_str())
+                .or_default()
+                .push((s.time_secs / median).ln());
+        }
+    }
+    log_ratios
+        .into_iter()
+        .map(|(player, ratios)| {
+            let skill = if ratios.len() >= min_games {
+                let mean = ratios.iter().sum::<f64>() / ratios.len() as f64;
+                mean.exp().clamp(MIN_SKILL, MAX_SKILL)
+            } else {
+                1.0
+            };
+            (player.to_string(), skill)
+        })
+        .collect()
+}
+
+/// Compute empirical difficulty for every puzzle with at least `min_results`
+/// verified results.
+pub fn compute_empirical(
+    samples: &[ResultSample],
+    min_results: usize,
+    min_player_games: usize,
+) -> HashMap<String, EmpiricalDifficulty> {
+    let skill = player_skill(samples, min_player_games);
+
+    let mut by_puzzle: HashMap<&str, Vec<&ResultSample>> = HashMap::new();
+    for s in samples {
+        by_puzzle.entry(s.puzzle_hash.as_str()).or_default().push(s);
+    }
+
+    by_puzzle
+        .into_iter()
+        .filter(|(_, results)| results.len() >= min_results)
+        .map(|(hash, results)| {
+            let wins: Vec<&&ResultSample> = results
+                .iter()
+                .filter(|s| s.won && s.time_secs > 0.0)
+                .collect();
+            let times = sorted(wins.iter().map(|s| s.time_secs).collect());
+            let normalized = sorted(
+                wins.iter()
+                    .map(|s| s.time_secs / skill.get(&s.player_id).copied().unwrap_or(1.0))
+                    .collect(),
+            );
+            let total = results.len() as f64;
+            let mistakes: u32 = results.iter().map(|s| s.mistakes).sum();
+            let time_at =
+                |values: &[f64], p: f64| (!values.is_empty()).then(|| percentile(values, p));
+
+            (
+                hash.to_string(),
+                EmpiricalDifficulty {
+                    samples: results.len(),
+                    p10_solve_time: time_at(&times, 0.1),
+                    median_solve_time: time_at(&times, 0.5),
+                    p90_solve_time: time_at(&times, 0.9),
+                    win_rate: wins.len() as f64 / total,
+                    mistake_rate: mistakes as f64 / total,
+                    normalized_solve_time: time_at(&normalized, 0.5),
+                },
+            )
+        })
+        .collect()
+}
+
+/// Assign each puzzle the tier whose typical normalized solve time is
+/// closest (in log space) to its own.
+///
+/// `puzzles` maps hash -> (SE tier, normalized median solve time). A tier's
+/// reference time is the median over its own puzzles, so tiers with no
+/// played puzzles are never assigned. Puzzles without wins (no time) are
+/// skipped.
+pub fn empirical_tiers(
+    puzzles: &HashMap<String, (String, Option<f64>)>,
+) -> HashMap<String, &'static str> {
+    let solved = || {
+        puzzles
+            .iter()
+            .filter_map(|(hash, (tier, time))| time.filter(|t| *t > 0.0).map(|t| (hash, tier, t)))
+    };
+    let mut tier_times: HashMap<&str, Vec<f64>> = HashMap::new();
+    for (_, tier, time) in solved() {
+        tier_times.entry(tier.as_str()).or_default().push(time.ln());
+    }
+    let references: Vec<(&'static str, f64)> = DIFFICULTY_TIERS
+        .iter()
+        .filter_map(|(name, ..)| {
+            tier_times
+                .get(name)
+                .map(|t| (*name, percentile(&sorted(t.clone()), 0.5)))
+        })
+        .collect();
+
+    solved()
+        .filter_map(|(hash, _, time)| {
+            let log_time = time.ln();
+            references
+                .iter()
+                .min_by(|a, b| (a.1 - log_time).abs().total_cmp(&(b.1 - log_time).abs()))
+                .map(|(tier, _)| (hash.clone(), *tier))
+        })
+        .collect()
+}
+
+/// Number of tiers between two tier names, or `None` if either is unknown.
+pub fn tier_distance(a: &str, b: &str) -> Option<usize> {
+    Some(tier_index(a)?.abs_diff(tier_index(b)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(puzzle: &str, player: &str, won: bool, time: f64, mistakes: u32) -> ResultSample {
+        ResultSample {
+            puzzle_hash: puzzle.into(),
+            player_id: player.into(),
+            won,
+            time_secs: time,
+            mistakes,
+        }
+    }
+
+    #[test]
+    fn test_percentile_interpolates() {
+        let v = [10.0, 20.0, 30.0, 40.0, 50.0];
+        assert!((percentile(&v, 0.5) - 30.0).abs() < 1e-9);
+        assert!((percentile(&v, 0.1) - 14.0).abs() < 1e-9);
+        assert!((percentile(&[], 0.5)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_fast_player_has_low_skill_factor() {
+        let samples = vec![
+            sample("a", "fast", true, 50.0, 0),
+            sample("a", "slow", true, 200.0, 0),
+            sample("b", "fast", true, 60.0, 0),
+            sample("b", "slow", true, 240.0, 0),
+        ];
+        let skill = player_skill(&samples, 2);
+        assert!(skill["fast"] < 1.0);
+        assert!(skill["slow"] > 1.0);
+    }
+
+    #[test]
+    fn test_compute_empirical_rates() {
+        let samples = vec![
+            sample("a", "p1", true, 100.0, 1),
+            sample("a", "p2", true, 300.0, 2),
+            sample("a", "p3", false, 500.0, 3),
+            sample("b", "p1", true, 100.0, 0),
+        ];
+        let result = compute_empirical(&samples, 3, 10);
+        assert_eq!(result.len(), 1, "puzzle b has too few results");
+        let a = &result["a"];
+        assert_eq!(a.samples, 3);
+        assert!((a.median_solve_time.unwrap() - 200.0).abs() < 1e-9);
+        assert!((a.win_rate - 2.0 / 3.0).abs() < 1e-9);
+        assert!((a.mistake_rate - 2.0).abs() < 1e-9);
+
+        // Played but never won: no solve times rather than 0-second ones
+        let losses: Vec<ResultSample> = (0..3)
+            .map(|i| sample("c", &format!("p{i}"), false, 400.0, 4))
+            .collect();
+        let c = &compute_empirical(&losses, 3, 10)["c"];
+        assert_eq!(c.median_solve_time, None);
+        assert_eq!(c.normalized_solve_time, None);
+        assert_eq!(c.win_rate, 0.0);
+    }
+
+    #[test]
+    fn test_empirical_tiers_flags_outlier() {
+        let mut puzzles = HashMap::new();
+        puzzles.insert("e1".to_string(), ("Easy".to_string(), Some(120.0)));
+        puzzles.insert("e2".to_string(), ("Easy".to_string(), Some(130.0)));
+        puzzles.insert("x1".to_string(), ("Extreme".to_string(), Some(3000.0)));
+        puzzles.insert("x2".to_string(), ("Extreme".to_string(), Some(2800.0)));
+        // Rated Extreme but played like an Easy puzzle
+        puzzles.insert("x3".to_string(), ("Extreme".to_string(), Some(125.0)));
+        // Never won
+        puzzles.insert("x4".to_string(), ("Extreme".to_string(), None));
+        let tiers = empirical_tiers(&puzzles);
+        assert!(!tiers.contains_key("x4"));
+        assert_eq!(tiers["e1"], "Easy");
+        assert_eq!(tiers["x1"], "Extreme");
+        assert_eq!(tiers["x3"], "Easy");
+        assert_eq!(tier_distance("Extreme", tiers["x3"]), Some(6));
+    }
+}
@@ -0,0 +1,117 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+/// One analyzed puzzle as written by the `export` subcommand.
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportRow {
+    pub puzzle_hash: String,
+    pub puzzle_string: String,
+    pub short_code: Option<String>,
+    pub tier: Option<String>,
+    pub max_technique: Option<String>,
+    pub max_se_rating: f64,
+    /// technique name -> REQUIRES_TECHNIQUE.count
+    pub techniques: BTreeMap<String, i64>,
+    pub play_count: i64,
+    pub win_count: i64,
+    pub avg_solve_time: f64,
+    pub win_rate: f64,
+}
+
+/// Column order for CSV output.
+pub const CSV_HEADER: &[&str] = &[
+    "puzzle_hash",
+    "puzzle_string",
+    "short_code",
+    "tier",
+    "max_technique",
+    "max_se_rating",
+    "techniques",
+    "play_count",
+    "win_count",
+    "avg_solve_time",
+    "win_rate",
+];
+
+impl ExportRow {
+    /// Render the row as a single CSV record (no trailing newline).
+    ///
+    /// Technique counts are packed into one column as `Name:count` pairs
+    /// separated by `;`, sorted by name.
+    pub fn to_csv_record(&self) -> String {
+        let techniques = self
+            .techniques
+            .iter()
+            .map(|(name, count)| format!("{name}:{count}"))
+            .collect::<Vec<_>>()
+            .join(";");
+
+        let fields = [
+            self.puzzle_hash.clone(),
+            self.puzzle_string.clone(),
+            self.short_code.clone().unwrap_or_default(),
+            self.tier.clone().unwrap_or_default(),
+            self.max_technique.clone().unwrap_or_default(),
+            self.max_se_rating.to_string(),
+            techniques,
+            self.play_count.to_string(),
+            self.win_count.to_string(),
+            format!("{:.2}", self.avg_solve_time),
+            format!("{:.4}", self.win_rate),
+        ];
+
+        fields
+            .iter()
+            .map(|f| csv_escape(f))
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+}
+
+/// Quote a CSV field if it contains a delimiter, quote, or line break (RFC 4180).
+pub fn csv_escape(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_row() -> ExportRow {
+        let mut techniques = BTreeMap::new();
+        techniques.insert("NakedSingle".to_string(), 40);
+        techniques.insert("HiddenSingle".to_string(), 11);
+        ExportRow {
+            puzzle_hash: "abc".into(),
+            puzzle_string: "0".repeat(81),
+            short_code: None,
+            tier: Some("Easy".into()),
+            max_technique: Some("Hidden Single".into()),
+            max_se_rating: 1.5,
+            techniques,
+            play_count: 3,
+            win_count: 2,
+            avg_solve_time: 123.456,
+            win_rate: 2.0 / 3.0,
+        }
+    }
+
+    #[test]
+    fn test_csv_escape() {
+        assert_eq!(csv_escape("plain"), "plain");
+        assert_eq!(csv_escape("a,b"), "\"a,b\"");
+        assert_eq!(csv_escape("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+
+    #[test]
+    fn test_csv_record_matches_header() {
+        let record = sample_row().to_csv_record();
+        assert_eq!(record.split(',').count(), CSV_HEADER.len());
+        assert!(record.contains("HiddenSingle:11;NakedSingle:40"));
+    }
+}
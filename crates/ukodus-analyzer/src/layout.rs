@@ -0,0 +1,242 @@
+//! Force-directed layout of the puzzle galaxy.
+//!
+//! Puzzles are points and `SHARES_TECHNIQUE_PROFILE` edges are springs
+//! weighted by similarity (Fruchterman-Reingold). Repulsion only acts
+//! between points closer than `REPULSION_RANGE` edge lengths, found through a
+//! uniform grid, so each pass is roughly linear in the number of puzzles.
+//!
+//! The result is deterministic. A point without a stored position starts
+//! next to its already-placed neighbours, or at a spot derived from its hash
+//! when it has none, so repeated runs give every visitor the same galaxy.
+
+use std::collections::{HashMap, VecDeque};
+
+/// Repulsion cut-off, in multiples of the ideal edge length.
+const REPULSION_RANGE: f64 = 3.0;
+/// Pull towards the origin, so disconnected components stay in view.
+const GRAVITY: f64 = 0.01;
+
+#[derive(Debug, Clone)]
+pub struct LayoutParams {
+    pub iterations: usize,
+    /// Ideal distance between two puzzles joined by an edge
+    pub edge_length: f64,
+}
+
+impl Default for LayoutParams {
+    fn default() -> Self {
+        Self {
+            iterations: 300,
+            edge_length: 30.0,
+        }
+    }
+}
+
+/// Lay out `ids` joined by `edges` (index pairs with similarity weights).
+///
+/// `initial[i]` is the stored position of point `i`, if any. With `pinned`,
+/// points that have one stay put and only new points move (incremental
+/// placement); otherwise every point relaxes, starting from its stored
+/// position so a full re-run drifts as little as possible.
+pub fn layout(
+    ids: &[String],
+    initial: &[Option<(f64, f64)>],
+    edges: &[(usize, usize, f64)],
+    pinned: bool,
+    params: &LayoutParams,
+) -> Vec<(f64, f64)> {
+    let n = ids.len();
+    let k = params.edge_length;
+    let mut adjacency: Vec<Vec<usize>> = vec![Vec::new(); n];
+    for &(a, b, _) in edges {
+        adjacency[a].push(b);
+        adjacency[b].push(a);
+    }
+
+    let mut pos = seed_positions(ids, initial, &adjacency, k);
+    let movable: Vec<bool> = (0..n).map(|i| !(pinned && initial[i].is_some())).collect();
+    if !movable.contains(&true) {
+        return pos;
+    }
+
+    let range = REPULSION_RANGE * k;
+    // Start hot enough to untangle the seed, then cool linearly
+    let start_temp = k * 2.0;
+    for iteration in 0..params.iterations {
+        let temp = start_temp * (1.0 - iteration as f64 / params.iterations as f64);
+        let mut disp = vec![(0.0f64, 0.0f64); n];
+
+        let mut grid: HashMap<(i64, i64), Vec<usize>> = HashMap::new();
+        for (i, p) in pos.iter().enumerate() {
+            grid.entry(cell_of(*p, range)).or_default().push(i);
+        }
+
+        for i in (0..n).filter(|&i| movable[i]) {
+            let (cx, cy) = cell_of(pos[i], range);
+            for gx in cx - 1..=cx + 1 {
+                for gy in cy - 1..=cy + 1 {
+                    for &j in grid.get(&(gx, gy)).into_iter().flatten() {
+                        if i == j {
+                            continue;
+                        }
+                        let (mut dx, mut dy) = (pos[i].0 - pos[j].0, pos[i].1 - pos[j].1);
+                        let mut dist = (dx * dx + dy * dy).sqrt();
+                        if dist < 1e-6 {
+                            // Coincident points: split them along a fixed direction
+                            let angle = (i as f64 - j as f64) * 2.399_963;
+                            (dx, dy, dist) = (angle.cos(), angle.sin(), 1.0);
+                        }
+                        if dist < range {
+                            let force = k * k / dist;
+                            disp[i].0 += dx / dist * force;
+                            disp[i].1 += dy / dist * force;
+                        }
+                    }
+                }
+            }
+        }
+
+        for &(a, b, weight) in edges {
+            let (dx, dy) = (pos[a].0 - pos[b].0, pos[a].1 - pos[b].1);
+            let dist = (dx * dx + dy * dy).sqrt().max(1e-6);
+            let force = dist * dist / k * weight;
+            let (fx, fy) = (dx / dist * force, dy / dist * force);
+            if movable[a] {
+                disp[a].0 -= fx;
+                disp[a].1 -= fy;
+            }
+            if movable[b] {
+                disp[b].0 += fx;
+                disp[b].1 += fy;
+            }
+        }
+
+        for i in (0..n).filter(|&i| movable[i]) {
+            let (dx, dy) = (
+                disp[i].0 - pos[i].0 * GRAVITY * k,
+                disp[i].1 - pos[i].1 * GRAVITY * k,
+            );
+            let len = (dx * dx + dy * dy).sqrt();
+            if len > 1e-9 {
+                let step = len.min(temp);
+                pos[i].0 += dx / len * step;
+                pos[i].1 += dy / len * step;
+            }
+        }
+    }
+    pos
+}
+
+/// Starting positions: stored ones as-is, new points beside the centroid of
+/// their placed neighbours, and the first point of an unplaced component at
+/// a hash-derived spot on a disc sized for the whole galaxy.
+fn seed_positions(
+    ids: &[String],
+    initial: &[Option<(f64, f64)>],
+    adjacency: &[Vec<usize>],
+    k: f64,
+) -> Vec<(f64, f64)> {
+    let n = ids.len();
+    let radius = (n as f64).sqrt() * k;
+    let mut pos: Vec<Option<(f64, f64)>> = initial.to_vec();
+
+    // Grow outwards from placed points, breadth-first
+    let mut queue: VecDeque<usize> = (0..n).filter(|&i| pos[i].is_some()).collect();
+    let mut next_unplaced = 0;
+    loop {
+        while let Some(u) = queue.pop_front() {
+            for &v in &adjacency[u] {
+                if pos[v].is_some() {
+                    continue;
+                }
+                let placed: Vec<(f64, f64)> = adjacency[v].iter().filter_map(|&j| pos[j]).collect();
+                let cx = placed.iter().map(|p| p.0).sum::<f64>() / placed.len() as f64;
+                let cy = placed.iter().map(|p| p.1).sum::<f64>() / placed.len() as f64;
+                let (u, _) = unit_pair(&ids[v]);
+                let angle = u * std::f64::consts::TAU;
+                pos[v] = Some((cx + angle.cos() * k * 0.5, cy + angle.sin() * k * 0.5));
+                queue.push_back(v);
+            }
+        }
+
+        while next_unplaced < n && pos[next_unplaced].is_some() {
+            next_unplaced += 1;
+        }
+        if next_unplaced == n {
+            break;
+        }
+        let (u, v) = unit_pair(&ids[next_unplaced]);
+        let angle = u * std::f64::consts::TAU;
+        let r = v.sqrt() * radius;
+        pos[next_unplaced] = Some((angle.cos() * r, angle.sin() * r));
+        queue.push_back(next_unplaced);
+    }
+
+    pos.into_iter().map(|p| p.unwrap_or_default()).collect()
+}
+
+fn cell_of(p: (f64, f64), size: f64) -> (i64, i64) {
+    ((p.0 / size).floor() as i64, (p.1 / size).floor() as i64)
+}
+
+/// Two stable pseudo-random numbers in `[0, 1)` from an id (FNV-1a).
+fn unit_pair(id: &str) -> (f64, f64) {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in id.bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    let hi = (hash >> 32) as f64 / (1u64 << 32) as f64;
+    let lo = (hash & 0xffff_ffff) as f64 / (1u64 << 32) as f64;
+    (hi, lo)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dist(a: (f64, f64), b: (f64, f64)) -> f64 {
+        ((a.0 - b.0).powi(2) + (a.1 - b.1).powi(2)).sqrt()
+    }
+
+    fn ids(n: usize) -> Vec<String> {
+        (0..n).map(|i| format!("puzzle-{i}")).collect()
+    }
+
+    #[test]
+    fn test_layout_groups_similar_puzzles() {
+        let ids = ids(6);
+        // Two triangles joined by one weak edge
+        let edges = vec![
+            (0, 1, 1.0),
+            (1, 2, 1.0),
+            (0, 2, 1.0),
+            (3, 4, 1.0),
+            (4, 5, 1.0),
+            (3, 5, 1.0),
+            (2, 3, 0.1),
+        ];
+        let params = LayoutParams::default();
+        let pos = layout(&ids, &[None; 6], &edges, false, &params);
+        assert_eq!(pos, layout(&ids, &[None; 6], &edges, false, &params));
+
+        let within = (dist(pos[0], pos[1]) + dist(pos[3], pos[4])) / 2.0;
+        let across = (dist(pos[0], pos[4]) + dist(pos[1], pos[5])) / 2.0;
+        assert!(within < across, "within {within:.1}, across {across:.1}");
+    }
+
+    #[test]
+    fn test_incremental_layout_pins_existing_points() {
+        let ids = ids(3);
+        let initial = vec![Some((0.0, 0.0)), Some((100.0, 0.0)), None];
+        let edges = vec![(0, 2, 1.0), (1, 2, 1.0)];
+        let pos = layout(&ids, &initial, &edges, true, &LayoutParams::default());
+        assert_eq!(pos[0], (0.0, 0.0));
+        assert_eq!(pos[1], (100.0, 0.0));
+        assert!(
+            dist(pos[2], (50.0, 0.0)) < 30.0,
+            "new point at {:?}",
+            pos[2]
+        );
+    }
+}
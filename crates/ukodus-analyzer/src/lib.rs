@@ -1,6 +1,16 @@
+pub mod community;
+pub mod cooccurrence;
+pub mod empirical;
+pub mod export;
+pub mod layout;
+pub mod path;
+
 use std::collections::{HashMap, HashSet};
 
 use sudoku_core::{Grid, HintType, Solver, Technique};
+use ukodus_grid::DIFFICULTY_TIERS;
+
+use path::{SolveStep, StepAction};
 
 /// Profile of techniques required to solve a puzzle.
 pub struct TechniqueProfile {
@@ -10,19 +20,23 @@ pub struct TechniqueProfile {
     pub max_technique: String,
     /// SE rating of the hardest technique
     pub max_se_rating: f32,
+    /// Every step in the order the solver applied it
+    pub path: Vec<SolveStep>,
 }
 
 /// Solve a puzzle step-by-step using the hint system, collecting every technique used.
 ///
-/// Returns `None` if the puzzle string is invalid or the solver gets stuck
-/// (which shouldn't happen for valid puzzles with unique solutions).
-pub fn collect_all_techniques(puzzle_string: &str) -> Option<TechniqueProfile> {
-    let mut grid = Grid::from_string(puzzle_string)?;
+/// Callers parse the grid (and report parse errors) themselves. Returns
+/// `None` if the solver gets stuck (which shouldn't happen for valid puzzles
+/// with unique solutions).
+pub fn collect_all_techniques(puzzle: &ukodus_grid::Grid) -> Option<TechniqueProfile> {
+    let mut grid = Grid::from_string(&puzzle.canonical())?;
     grid.recalculate_candidates();
 
     let solver = Solver;
     let mut techniques: HashMap<String, u32> = HashMap::new();
     let mut max_technique: Technique = Technique::NakedSingle;
+    let mut path: Vec<SolveStep> = Vec::new();
 
     let mut iterations = 0;
     const MAX_ITERATIONS: usize = 10_000;
@@ -46,9 +60,19 @@ pub fn collect_all_techniques(puzzle_string: &str) -> Option<TechniqueProfile> {
         // Apply the hint to the grid
         match hint.hint_type {
             HintType::SetValue { pos, value } => {
+                path.push(SolveStep {
+                    technique: technique_enum_name(tech),
+                    cell: (pos.row * 9 + pos.col) as u8,
+                    action: StepAction::Place(value),
+                });
                 grid.set_cell_unchecked(pos, Some(value));
             }
             HintType::EliminateCandidates { pos, values } => {
+                path.push(SolveStep {
+                    technique: technique_enum_name(tech),
+                    cell: (pos.row * 9 + pos.col) as u8,
+                    action: StepAction::Eliminate(values.clone()),
+                });
                 for v in values {
                     grid.cell_mut(pos).remove_candidate(v);
                 }
@@ -63,6 +87,7 @@ pub fn collect_all_techniques(puzzle_string: &str) -> Option<TechniqueProfile> {
         techniques,
         max_technique: max_technique.to_string(),
         max_se_rating: max_technique.se_rating(),
+        path,
     })
 }
 
@@ -79,6 +104,23 @@ pub fn jaccard_similarity(a: &HashSet<String>, b: &HashSet<String>) -> f64 {
     intersection as f64 / union as f64
 }
 
+/// Map an SE rating onto its difficulty tier name.
+///
+/// Ratings below the first tier clamp to "Beginner"; ratings at or above the
+/// last tier's upper bound clamp to "Extreme".
+pub fn tier_for_se_rating(se: f32) -> &'static str {
+    let se = se as f64;
+    DIFFICULTY_TIERS
+        .iter()
+        .find(|(_, min_se, max_se, _)| *min_se <= se && se < *max_se)
+        .map(|(name, ..)| *name)
+        .unwrap_or(if se < DIFFICULTY_TIERS[0].1 {
+            DIFFICULTY_TIERS[0].0
+        } else {
+            DIFFICULTY_TIERS[DIFFICULTY_TIERS.len() - 1].0
+        })
+}
+
 /// All 45 techniques with their family and ordinal (position within family).
 pub struct TechniqueSeed {
     pub name: &'static str,
@@ -158,6 +200,71 @@ pub fn all_technique_seeds() -> Vec<TechniqueSeed> {
     seeds
 }
 
+/// Curated `PREREQUISITE_OF` edges as `(prerequisite, technique)` enum names:
+/// the first technique is worth knowing before learning the second.
+///
+/// Kept acyclic; every name must appear in [`all_technique_seeds`].
+pub const TECHNIQUE_PREREQUISITES: &[(&str, &str)] = &[
+    // Singles and subsets
+    ("NakedSingle", "HiddenSingle"),
+    ("HiddenSingle", "NakedPair"),
+    ("HiddenSingle", "HiddenPair"),
+    ("HiddenSingle", "PointingPair"),
+    ("HiddenSingle", "BoxLineReduction"),
+    ("NakedPair", "NakedTriple"),
+    ("NakedTriple", "NakedQuad"),
+    ("HiddenPair", "HiddenTriple"),
+    ("HiddenTriple", "HiddenQuad"),
+    // Fish
+    ("PointingPair", "XWing"),
+    ("BoxLineReduction", "XWing"),
+    ("XWing", "FinnedXWing"),
+    ("XWing", "Swordfish"),
+    ("Swordfish", "Jellyfish"),
+    ("Swordfish", "FinnedSwordfish"),
+    ("FinnedXWing", "FinnedSwordfish"),
+    ("Jellyfish", "FinnedJellyfish"),
+    ("FinnedSwordfish", "FinnedJellyfish"),
+    ("FinnedXWing", "SiameseFish"),
+    ("Swordfish", "FrankenFish"),
+    ("FrankenFish", "MutantFish"),
+    ("FinnedSwordfish", "KrakenFish"),
+    ("AIC", "KrakenFish"),
+    // Wings
+    ("NakedPair", "XYWing"),
+    ("XYWing", "XYZWing"),
+    ("XYZWing", "WXYZWing"),
+    ("XYWing", "WWing"),
+    // Chains
+    ("XWing", "XChain"),
+    ("XChain", "ThreeDMedusa"),
+    ("XChain", "AIC"),
+    ("XYWing", "AIC"),
+    // Rectangles
+    ("PointingPair", "EmptyRectangle"),
+    ("NakedPair", "UniqueRectangle"),
+    ("UniqueRectangle", "AvoidableRectangle"),
+    ("UniqueRectangle", "HiddenRectangle"),
+    ("UniqueRectangle", "ExtendedUniqueRectangle"),
+    ("UniqueRectangle", "BivalueUniversalGrave"),
+    // Almost locked sets
+    ("NakedTriple", "AlsXz"),
+    ("AlsXz", "AlsXyWing"),
+    ("AlsXyWing", "AlsChain"),
+    ("AIC", "AlsChain"),
+    ("AlsXz", "DeathBlossom"),
+    ("AlsXz", "SueDeCoq"),
+    ("BoxLineReduction", "SueDeCoq"),
+    ("NakedPair", "AlignedPairExclusion"),
+    ("AlignedPairExclusion", "AlignedTripletExclusion"),
+    // Forcing chains
+    ("AIC", "NishioForcingChain"),
+    ("AIC", "CellForcingChain"),
+    ("CellForcingChain", "RegionForcingChain"),
+    ("RegionForcingChain", "DynamicForcingChain"),
+    ("NishioForcingChain", "DynamicForcingChain"),
+];
+
 /// Stable enum variant name (PascalCase) for storage as a unique key.
 fn technique_enum_name(t: Technique) -> &'static str {
     match t {
@@ -268,9 +375,16 @@ mod tests {
     fn test_collect_easy_puzzle() {
         // A well-known easy puzzle solvable with singles only
         let puzzle = "530070000600195000098000060800060003400803001700020006060000280000419005000080079";
-        let profile = collect_all_techniques(puzzle).expect("should solve");
+        let grid = ukodus_grid::Grid::parse(puzzle).unwrap();
+        let profile = collect_all_techniques(&grid).expect("should solve");
         assert!(profile.max_se_rating <= 3.0, "easy puzzle should have low SE rating");
         assert!(!profile.techniques.is_empty());
+        let placements = profile
+            .path
+            .iter()
+            .filter(|s| matches!(s.action, StepAction::Place(_)))
+            .count();
+        assert_eq!(placements, puzzle.bytes().filter(|b| *b == b'0').count());
     }
 
     #[test]
@@ -294,6 +408,49 @@ mod tests {
         assert!((jaccard_similarity(&a, &b)).abs() < f64::EPSILON);
     }
 
+    #[test]
+    fn test_tier_for_se_rating() {
+        assert_eq!(tier_for_se_rating(1.2), "Beginner");
+        assert_eq!(tier_for_se_rating(2.0), "Easy");
+        assert_eq!(tier_for_se_rating(5.4), "Hard");
+        assert_eq!(tier_for_se_rating(7.9), "Master");
+        // Out-of-range ratings clamp to the outer tiers
+        assert_eq!(tier_for_se_rating(0.0), "Beginner");
+        assert_eq!(tier_for_se_rating(11.0), "Extreme");
+    }
+
+    #[test]
+    fn test_technique_prerequisites_form_a_dag() {
+        let names: HashSet<&str> = all_technique_seeds().iter().map(|s| s.name).collect();
+        let mut indegree: HashMap<&str, usize> = names.iter().map(|n| (*n, 0)).collect();
+        for (prerequisite, technique) in TECHNIQUE_PREREQUISITES {
+            assert!(names.contains(prerequisite), "unknown technique {prerequisite}");
+            assert!(names.contains(technique), "unknown technique {technique}");
+            *indegree.get_mut(technique).unwrap() += 1;
+        }
+
+        // Kahn's algorithm visits every technique only if there is no cycle
+        let mut ready: Vec<&str> = indegree
+            .iter()
+            .filter(|(_, d)| **d == 0)
+            .map(|(n, _)| *n)
+            .collect();
+        let mut visited = 0;
+        while let Some(name) = ready.pop() {
+            visited += 1;
+            for (prerequisite, technique) in TECHNIQUE_PREREQUISITES {
+                if *prerequisite == name {
+                    let d = indegree.get_mut(technique).unwrap();
+                    *d -= 1;
+                    if *d == 0 {
+                        ready.push(technique);
+                    }
+                }
+            }
+        }
+        assert_eq!(visited, names.len());
+    }
+
     #[test]
     fn test_technique_seeds_count() {
         let seeds = all_technique_seeds();
@@ -1,12 +1,25 @@
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
 use std::sync::Arc;
 
 use anyhow::{Context, Result};
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
 use neo4rs::{query, Graph, Query};
 use tracing::{info, warn};
 
-use ukodus_analyzer::{all_technique_seeds, collect_all_techniques, jaccard_similarity};
+use ukodus_analyzer::community::{label_propagation, summarize, Member};
+use ukodus_analyzer::cooccurrence::co_occurrence;
+use ukodus_analyzer::empirical::{compute_empirical, empirical_tiers, tier_distance, ResultSample};
+use ukodus_analyzer::export::{ExportRow, CSV_HEADER};
+use ukodus_analyzer::layout::{layout as force_layout, LayoutParams};
+use ukodus_analyzer::path::encode_path;
+use ukodus_analyzer::{
+    all_technique_seeds, collect_all_techniques, jaccard_similarity, tier_for_se_rating,
+    TECHNIQUE_PREREQUISITES,
+};
+use ukodus_grid::DIFFICULTY_TIERS;
 
 #[derive(Parser)]
 #[command(name = "ukodus-analyzer", about = "Batch technique extraction and similarity analysis")]
@@ -30,9 +43,76 @@ enum Command {
     AnalyzeBatch {
         #[arg(long, default_value = "100")]
         batch_size: usize,
+        /// Flag puzzles whose client-reported SE rating differs from the
+        /// computed one by more than this amount
+        #[arg(long, default_value = "0.5")]
+        discrepancy_threshold: f32,
     },
+    /// Compute observed difficulty per puzzle from verified game results
+    EmpiricalDifficulty {
+        /// Minimum verified results before a puzzle gets an empirical rating
+        #[arg(long, default_value = "5")]
+        min_results: usize,
+        /// Minimum wins before a player's times are skill-normalized
+        #[arg(long, default_value = "3")]
+        min_player_games: usize,
+        /// Flag puzzles whose empirical tier is at least this many tiers
+        /// away from their SE tier
+        #[arg(long, default_value = "2")]
+        mismatch_tiers: usize,
+    },
+    /// Stream every analyzed puzzle with its technique profile as JSONL or CSV
+    Export {
+        #[arg(long, value_enum, default_value = "jsonl")]
+        format: ExportFormat,
+        /// Output file (defaults to stdout)
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+        /// Only export puzzles in this DifficultyTier (e.g. "Expert")
+        #[arg(long)]
+        tier: Option<String>,
+        /// Only export puzzles requiring this technique (enum or display name)
+        #[arg(long)]
+        technique: Option<String>,
+    },
+    /// Compute galaxy coordinates from SHARES_TECHNIQUE_PROFILE edges
+    Layout {
+        /// Keep puzzles that already have coordinates fixed and only place
+        /// new ones near their neighbors
+        #[arg(long)]
+        incremental: bool,
+        #[arg(long, default_value = "300")]
+        iterations: usize,
+        /// Ideal distance between two similar puzzles
+        #[arg(long, default_value = "30")]
+        edge_length: f64,
+    },
+    /// Group similar puzzles into Cluster nodes via label propagation
+    Communities {
+        /// Communities smaller than this are left unclustered
+        #[arg(long, default_value = "3")]
+        min_size: usize,
+        #[arg(long, default_value = "50")]
+        max_iterations: usize,
+    },
+    /// Compute CO_OCCURS_WITH edges between techniques required together
+    /// (classic puzzles only)
+    CoOccurrence {
+        /// Pairs required together by fewer puzzles than this get no edge
+        #[arg(long, default_value = "3")]
+        min_count: u64,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ExportFormat {
+    Jsonl,
+    Csv,
 }
 
+/// Rows written per UNWIND batch.
+const WRITE_BATCH: usize = 1000;
+
 /// Execute a write-only Cypher query, consuming the result stream.
 async fn run_query(graph: &Graph, q: Query) -> Result<()> {
     let mut result = graph.execute(q).await?;
@@ -47,6 +127,8 @@ async fn main() {
             tracing_subscriber::EnvFilter::try_from_default_env()
                 .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("info")),
         )
+        // Keep stdout free for `export` output
+        .with_writer(io::stderr)
         .init();
 
     if let Err(e) = run().await {
@@ -67,7 +149,46 @@ async fn run() -> Result<()> {
 
     match cli.command {
         Command::SeedTechniques => seed_techniques(&graph).await?,
-        Command::AnalyzeBatch { batch_size } => analyze_batch(&graph, batch_size).await?,
+        Command::AnalyzeBatch {
+            batch_size,
+            discrepancy_threshold,
+        } => analyze_batch(&graph, batch_size, discrepancy_threshold).await?,
+        Command::EmpiricalDifficulty {
+            min_results,
+            min_player_games,
+            mismatch_tiers,
+        } => empirical_difficulty(&graph, min_results, min_player_games, mismatch_tiers).await?,
+        Command::Export {
+            format,
+            output,
+            tier,
+            technique,
+        } => {
+            export(
+                &graph,
+                format,
+                output,
+                tier.as_deref(),
+                technique.as_deref(),
+            )
+            .await?
+        }
+        Command::Layout {
+            incremental,
+            iterations,
+            edge_length,
+        } => {
+            let params = LayoutParams {
+                iterations,
+                edge_length,
+            };
+            galaxy_layout(&graph, incremental, &params).await?
+        }
+        Command::Communities {
+            min_size,
+            max_iterations,
+        } => communities(&graph, min_size, max_iterations).await?,
+        Command::CoOccurrence { min_count } => technique_co_occurrence(&graph, min_count).await?,
     }
 
     Ok(())
@@ -134,18 +255,7 @@ async fn seed_techniques(graph: &Graph) -> Result<()> {
     info!("Created {} Technique nodes with BELONGS_TO edges", seeds.len());
 
     // 3. Create DifficultyTier nodes
-    let tiers = [
-        ("Beginner", 1.0_f64, 2.0_f64, "#86efac"),
-        ("Easy", 2.0, 2.9, "#22c55e"),
-        ("Medium", 2.9, 3.5, "#f59e0b"),
-        ("Intermediate", 3.5, 4.5, "#fb923c"),
-        ("Hard", 4.5, 5.5, "#ef4444"),
-        ("Expert", 5.5, 6.5, "#dc2626"),
-        ("Master", 6.5, 8.0, "#9333ea"),
-        ("Extreme", 8.0, 11.0, "#1e293b"),
-    ];
-
-    for (name, min_se, max_se, color) in &tiers {
+    for (name, min_se, max_se, color) in DIFFICULTY_TIERS {
         run_query(
             graph,
             query(
@@ -160,23 +270,60 @@ async fn seed_techniques(graph: &Graph) -> Result<()> {
         .await
         .context("Failed to create DifficultyTier")?;
     }
-    info!("Created {} DifficultyTier nodes", tiers.len());
+    info!("Created {} DifficultyTier nodes", DIFFICULTY_TIERS.len());
+
+    // 4. Replace curated PREREQUISITE_OF edges
+    run_query(graph, query("MATCH (:Technique)-[r:PREREQUISITE_OF]->(:Technique) DELETE r"))
+        .await
+        .context("Failed to clear PREREQUISITE_OF edges")?;
+    for (prerequisite, technique) in TECHNIQUE_PREREQUISITES {
+        run_query(
+            graph,
+            query(
+                "MATCH (a:Technique {name: $prerequisite})
+                 MATCH (b:Technique {name: $technique})
+                 MERGE (a)-[:PREREQUISITE_OF]->(b)",
+            )
+            .param("prerequisite", *prerequisite)
+            .param("technique", *technique),
+        )
+        .await
+        .context("Failed to create PREREQUISITE_OF edge")?;
+    }
+    info!("Created {} PREREQUISITE_OF edges", TECHNIQUE_PREREQUISITES.len());
 
     info!("Seed complete.");
     Ok(())
 }
 
 /// Analyze a batch of puzzles: extract technique profiles and create graph edges.
-async fn analyze_batch(graph: &Graph, batch_size: usize) -> Result<()> {
+///
+/// The computed max SE rating is authoritative: it overwrites the
+/// client-reported `se_rating` and `difficulty`, keeping the originals in
+/// `reported_se_rating` / `reported_difficulty`.
+async fn analyze_batch(graph: &Graph, batch_size: usize, discrepancy_threshold: f32) -> Result<()> {
+    // Pick up discovered puzzles the API did not manage to queue itself
+    run_query(
+        graph,
+        query(
+            "MATCH (p:Puzzle {state: 'discovered', variant: 'classic'})
+             SET p.state = 'queued', p.state_changed_at = datetime()",
+        ),
+    )
+    .await
+    .context("Failed to queue discovered puzzles")?;
+
     info!("Fetching up to {} puzzles needing analysis...", batch_size);
 
-    // 1. Fetch puzzles where needs_analysis = true
+    // 1. Fetch queued puzzles, oldest first. sudoku-core's solver only
+    // knows classic rules, so only classic puzzles are ever queued.
     let mut result = graph
         .execute(
             query(
-                "MATCH (p:Puzzle)
-                 WHERE p.needs_analysis = true
-                 RETURN p.puzzle_string AS puzzle_string, elementId(p) AS id
+                "MATCH (p:Puzzle {state: 'queued', variant: 'classic'})
+                 RETURN p.puzzle_string AS puzzle_string, elementId(p) AS id,
+                        COALESCE(p.reported_se_rating, p.se_rating) AS reported_se
+                 ORDER BY p.state_changed_at ASC
                  LIMIT $limit",
             )
             .param("limit", batch_size as i64),
@@ -188,12 +335,18 @@ async fn analyze_batch(graph: &Graph, batch_size: usize) -> Result<()> {
     struct PuzzleRow {
         id: String,
         puzzle_string: String,
+        reported_se: Option<f64>,
     }
     let mut puzzles = Vec::new();
     while let Some(row) = result.next().await? {
         let id: String = row.get("id")?;
         let puzzle_string: String = row.get("puzzle_string")?;
-        puzzles.push(PuzzleRow { id, puzzle_string });
+        let reported_se: Option<f64> = row.get("reported_se").ok();
+        puzzles.push(PuzzleRow {
+            id,
+            puzzle_string,
+            reported_se,
+        });
     }
 
     if puzzles.is_empty() {
@@ -210,7 +363,14 @@ async fn analyze_batch(graph: &Graph, batch_size: usize) -> Result<()> {
     let mut analyzed: Vec<AnalyzedPuzzle> = Vec::new();
 
     for (i, puzzle) in puzzles.iter().enumerate() {
-        let profile = match collect_all_techniques(&puzzle.puzzle_string) {
+        let profile = match ukodus_grid::Grid::parse(&puzzle.puzzle_string) {
+            Ok(grid) => collect_all_techniques(&grid),
+            Err(e) => {
+                warn!("Puzzle {} has an unparseable grid: {}", &puzzle.id, e);
+                None
+            }
+        };
+        let profile = match profile {
             Some(p) => p,
             None => {
                 warn!(
@@ -218,13 +378,13 @@ async fn analyze_batch(graph: &Graph, batch_size: usize) -> Result<()> {
                     i + 1,
                     &puzzle.id
                 );
-                // Mark as analyzed to avoid retrying broken puzzles forever
+                // Mark as failed to avoid retrying broken puzzles forever
                 run_query(
                     graph,
                     query(
-                        "MATCH (p:Puzzle)
+                        "MATCH (p:Puzzle {state: 'queued'})
                          WHERE elementId(p) = $id
-                         SET p.needs_analysis = false, p.analysis_error = true",
+                         SET p.state = 'failed', p.state_changed_at = datetime()",
                     )
                     .param("id", puzzle.id.clone()),
                 )
@@ -266,32 +426,64 @@ async fn analyze_batch(graph: &Graph, batch_size: usize) -> Result<()> {
         .context("Failed to create MAX_TECHNIQUE edge")?;
 
         // Create IN_TIER edge based on max SE rating
+        let tier = tier_for_se_rating(profile.max_se_rating);
         run_query(
             graph,
             query(
                 "MATCH (p:Puzzle) WHERE elementId(p) = $pid
-                 MATCH (d:DifficultyTier)
-                 WHERE d.min_se <= $se AND $se < d.max_se
+                 OPTIONAL MATCH (p)-[old:IN_TIER]->(:DifficultyTier)
+                 DELETE old
+                 WITH DISTINCT p
+                 MATCH (d:DifficultyTier {name: $tier})
                  MERGE (p)-[:IN_TIER]->(d)",
             )
             .param("pid", puzzle.id.clone())
-            .param("se", profile.max_se_rating as f64),
+            .param("tier", tier),
         )
         .await
         .context("Failed to create IN_TIER edge")?;
 
-        // Mark puzzle as analyzed
+        // Record how far the client-reported rating was from the computed one
+        let discrepancy = puzzle
+            .reported_se
+            .map(|reported| (reported - profile.max_se_rating as f64).abs())
+            .filter(|delta| *delta > discrepancy_threshold as f64);
+        if let Some(delta) = discrepancy {
+            warn!(
+                "Puzzle {} reported SE {:.1} but computed {:.1} (delta {:.1})",
+                &puzzle.id,
+                puzzle.reported_se.unwrap_or(0.0),
+                profile.max_se_rating,
+                delta
+            );
+        }
+
+        // Mark puzzle as analyzed and make the computed rating authoritative
         run_query(
             graph,
             query(
-                "MATCH (p:Puzzle) WHERE elementId(p) = $pid
-                 SET p.needs_analysis = false,
+                "MATCH (p:Puzzle {state: 'queued'}) WHERE elementId(p) = $pid
+                 SET p.state = 'analyzed',
+                     p.state_changed_at = datetime(),
                      p.max_se_rating = $se,
-                     p.max_technique = $tname",
+                     p.max_technique = $tname,
+                     p.reported_se_rating = COALESCE(p.reported_se_rating, p.se_rating),
+                     p.reported_difficulty = COALESCE(p.reported_difficulty, p.difficulty),
+                     p.se_rating = $se,
+                     p.difficulty = $tier,
+                     p.rating_discrepancy = $discrepancy,
+                     p.rated_at = datetime(),
+                     p.solve_path = $path,
+                     p.solve_path_steps = $steps,
+                     p.updated_at = datetime()",
             )
             .param("pid", puzzle.id.clone())
+            .param("path", encode_path(&profile.path))
+            .param("steps", profile.path.len() as i64)
             .param("se", profile.max_se_rating as f64)
-            .param("tname", profile.max_technique.clone()),
+            .param("tname", profile.max_technique.clone())
+            .param("tier", tier)
+            .param("discrepancy", discrepancy),
         )
         .await
         .context("Failed to update puzzle")?;
@@ -327,6 +519,7 @@ async fn analyze_batch(graph: &Graph, batch_size: usize) -> Result<()> {
                             "MATCH (a:Puzzle) WHERE elementId(a) = $aid
                              MATCH (b:Puzzle) WHERE elementId(b) = $bid
                              MERGE (a)-[r:SHARES_TECHNIQUE_PROFILE]-(b)
+                             ON CREATE SET r.created_at = datetime()
                              SET r.similarity = $sim",
                         )
                         .param("aid", analyzed[i].id.clone())
@@ -345,3 +538,575 @@ async fn analyze_batch(graph: &Graph, batch_size: usize) -> Result<()> {
     info!("Batch analysis complete.");
     Ok(())
 }
+
+/// Compute empirical difficulty from verified results and store it on each Puzzle.
+async fn empirical_difficulty(
+    graph: &Graph,
+    min_results: usize,
+    min_player_games: usize,
+    mismatch_tiers: usize,
+) -> Result<()> {
+    info!("Fetching verified game results...");
+    let mut result = graph
+        .execute(query(
+            "MATCH (r:GameResult)-[:FOR_PUZZLE]->(p:Puzzle)
+             WHERE r.verified = true
+             RETURN p.hash AS hash, p.difficulty AS tier, r.player_id AS player_id,
+                    r.result = 'Win' AS won, r.time_secs AS time_secs,
+                    r.mistakes AS mistakes",
+        ))
+        .await
+        .context("Failed to query game results")?;
+
+    let mut samples = Vec::new();
+    let mut se_tiers: HashMap<String, String> = HashMap::new();
+    while let Some(row) = result.next().await? {
+        let hash: String = row.get("hash")?;
+        if let Ok(tier) = row.get::<String>("tier") {
+            se_tiers.insert(hash.clone(), tier);
+        }
+        samples.push(ResultSample {
+            puzzle_hash: hash,
+            player_id: row.get("player_id").unwrap_or_default(),
+            won: row.get("won").unwrap_or(false),
+            time_secs: row.get::<i64>("time_secs").unwrap_or(0) as f64,
+            mistakes: row.get::<i64>("mistakes").unwrap_or(0) as u32,
+        });
+    }
+    info!("Loaded {} verified results", samples.len());
+
+    let empirical = compute_empirical(&samples, min_results, min_player_games);
+    if empirical.is_empty() {
+        info!("No puzzles have at least {} verified results.", min_results);
+        return Ok(());
+    }
+
+    let tier_input: HashMap<String, (String, Option<f64>)> = empirical
+        .iter()
+        .filter_map(|(hash, e)| {
+            se_tiers
+                .get(hash)
+                .map(|tier| (hash.clone(), (tier.clone(), e.normalized_solve_time)))
+        })
+        .collect();
+    let observed_tiers = empirical_tiers(&tier_input);
+
+    let mut mismatches = 0;
+    for (hash, e) in &empirical {
+        let observed = observed_tiers.get(hash).copied();
+        let mismatch = match (observed, se_tiers.get(hash)) {
+            (Some(observed), Some(se_tier)) => {
+                tier_distance(observed, se_tier).is_some_and(|d| d >= mismatch_tiers)
+            }
+            _ => false,
+        };
+        if mismatch {
+            mismatches += 1;
+            warn!(
+                "Puzzle {} is rated {} but plays like {}",
+                hash,
+                se_tiers.get(hash).map(String::as_str).unwrap_or("?"),
+                observed.unwrap_or("?")
+            );
+        }
+
+        run_query(
+            graph,
+            query(
+                "MATCH (p:Puzzle {hash: $hash})
+                 SET p.empirical_samples = $samples,
+                     p.empirical_p10_time = $p10,
+                     p.empirical_median_time = $median,
+                     p.empirical_p90_time = $p90,
+                     p.empirical_win_rate = $win_rate,
+                     p.empirical_mistake_rate = $mistake_rate,
+                     p.empirical_normalized_time = $normalized,
+                     p.empirical_tier = $tier,
+                     p.tier_mismatch = $mismatch,
+                     p.empirical_updated_at = datetime(),
+                     p.updated_at = datetime()",
+            )
+            .param("hash", hash.clone())
+            .param("samples", e.samples as i64)
+            .param("p10", e.p10_solve_time)
+            .param("median", e.median_solve_time)
+            .param("p90", e.p90_solve_time)
+            .param("win_rate", e.win_rate)
+            .param("mistake_rate", e.mistake_rate)
+            .param("normalized", e.normalized_solve_time)
+            .param("tier", observed)
+            .param("mismatch", mismatch),
+        )
+        .await
+        .context("Failed to store empirical difficulty")?;
+    }
+
+    info!(
+        "Stored empirical difficulty for {} puzzles ({} tier mismatches)",
+        empirical.len(),
+        mismatches
+    );
+    Ok(())
+}
+
+#[derive(Default)]
+struct LayoutGroup {
+    hashes: Vec<String>,
+    stored: Vec<Option<(f64, f64)>>,
+}
+
+/// Run the force-directed layout over public puzzles and store `p.x`/`p.y`.
+///
+/// Each variant is laid out on its own, since the galaxy never mixes them.
+async fn galaxy_layout(graph: &Graph, incremental: bool, params: &LayoutParams) -> Result<()> {
+    info!("Fetching puzzles for layout...");
+    let mut result = graph
+        .execute(query(
+            "MATCH (p:Puzzle)
+             WHERE p.state IN ['discovered', 'queued', 'analyzed']
+             RETURN p.hash AS hash, coalesce(p.variant, 'classic') AS variant,
+                    p.x AS x, p.y AS y
+             ORDER BY p.hash",
+        ))
+        .await
+        .context("Failed to query puzzles for layout")?;
+
+    // variant -> puzzle hashes and their stored positions
+    let mut groups: BTreeMap<String, LayoutGroup> = BTreeMap::new();
+    let mut index: HashMap<String, (String, usize)> = HashMap::new();
+    while let Some(row) = result.next().await? {
+        let hash: String = row.get("hash")?;
+        let variant: String = row.get("variant").unwrap_or_else(|_| "classic".to_string());
+        let stored = match (row.get::<f64>("x"), row.get::<f64>("y")) {
+            (Ok(x), Ok(y)) => Some((x, y)),
+            _ => None,
+        };
+        let group = groups.entry(variant.clone()).or_default();
+        index.insert(hash.clone(), (variant, group.hashes.len()));
+        group.hashes.push(hash);
+        group.stored.push(stored);
+    }
+
+    let mut result = graph
+        .execute(query(
+            "MATCH (a:Puzzle)-[r:SHARES_TECHNIQUE_PROFILE]-(b:Puzzle)
+             WHERE a.hash < b.hash
+             RETURN a.hash AS a, b.hash AS b, coalesce(r.similarity, 0.5) AS similarity",
+        ))
+        .await
+        .context("Failed to query similarity edges")?;
+
+    let mut edges: HashMap<String, Vec<(usize, usize, f64)>> = HashMap::new();
+    while let Some(row) = result.next().await? {
+        let a: String = row.get("a")?;
+        let b: String = row.get("b")?;
+        let (Some((va, ia)), Some((vb, ib))) = (index.get(&a), index.get(&b)) else {
+            continue;
+        };
+        if va == vb {
+            edges
+                .entry(va.clone())
+                .or_default()
+                .push((*ia, *ib, row.get("similarity").unwrap_or(0.5)));
+        }
+    }
+
+    for (variant, LayoutGroup { hashes, stored }) in &groups {
+        let variant_edges = edges.remove(variant).unwrap_or_default();
+        let new = stored.iter().filter(|p| p.is_none()).count();
+        if incremental && new == 0 {
+            info!("{variant}: all {} puzzles already placed", hashes.len());
+            continue;
+        }
+        info!(
+            "{variant}: laying out {} puzzles ({} new) over {} edges",
+            hashes.len(),
+            new,
+            variant_edges.len()
+        );
+        let positions = force_layout(hashes, stored, &variant_edges, incremental, params);
+
+        // In incremental mode only the new puzzles moved
+        let changed: Vec<usize> = (0..hashes.len())
+            .filter(|&i| !incremental || stored[i].is_none())
+            .collect();
+        for chunk in changed.chunks(WRITE_BATCH) {
+            // Two decimals is far below a pixel at any zoom
+            let round = |v: f64| (v * 100.0).round() / 100.0;
+            run_query(
+                graph,
+                query(
+                    "UNWIND range(0, size($hashes) - 1) AS i
+                     MATCH (p:Puzzle {hash: $hashes[i]})
+                     SET p.x = $xs[i], p.y = $ys[i],
+                         p.location = point({x: $xs[i], y: $ys[i]}),
+                         p.layout_at = datetime()",
+                )
+                .param("hashes", chunk.iter().map(|&i| hashes[i].clone()).collect::<Vec<_>>())
+                .param("xs", chunk.iter().map(|&i| round(positions[i].0)).collect::<Vec<_>>())
+                .param("ys", chunk.iter().map(|&i| round(positions[i].1)).collect::<Vec<_>>()),
+            )
+            .await
+            .context("Failed to store layout positions")?;
+        }
+        info!("{variant}: stored {} positions", changed.len());
+    }
+
+    info!("Layout complete.");
+    Ok(())
+}
+
+/// How many dominant techniques each Cluster records.
+const DOMINANT_TECHNIQUES: usize = 3;
+
+/// Detect communities in the similarity graph and store them as `Cluster`
+/// nodes joined to their members by `IN_CLUSTER`.
+///
+/// A cluster's id is derived from its smallest member hash, so it survives
+/// re-runs as long as that puzzle stays in it. Clusters that no longer exist
+/// are removed at the end of the run.
+async fn communities(graph: &Graph, min_size: usize, max_iterations: usize) -> Result<()> {
+    info!("Fetching puzzles for community detection...");
+    let mut result = graph
+        .execute(query(
+            "MATCH (p:Puzzle)
+             WHERE p.state IN ['discovered', 'queued', 'analyzed']
+             OPTIONAL MATCH (p)-[:MAX_TECHNIQUE]->(mt:Technique)
+             RETURN p.hash AS hash, coalesce(p.variant, 'classic') AS variant,
+                    coalesce(p.max_se_rating, p.se_rating, 0.0) AS se_rating,
+                    mt.name AS max_technique, p.x AS x, p.y AS y
+             ORDER BY p.hash",
+        ))
+        .await
+        .context("Failed to query puzzles for community detection")?;
+
+    struct PuzzleRow {
+        hash: String,
+        variant: String,
+        se_rating: f64,
+        max_technique: Option<String>,
+        position: Option<(f64, f64)>,
+    }
+    let mut puzzles = Vec::new();
+    while let Some(row) = result.next().await? {
+        let position = match (row.get::<f64>("x"), row.get::<f64>("y")) {
+            (Ok(x), Ok(y)) => Some((x, y)),
+            _ => None,
+        };
+        puzzles.push(PuzzleRow {
+            hash: row.get("hash")?,
+            variant: row.get("variant").unwrap_or_else(|_| "classic".to_string()),
+            se_rating: row.get("se_rating").unwrap_or(0.0),
+            max_technique: row.get("max_technique").ok(),
+            position,
+        });
+    }
+    let index: HashMap<&str, usize> = puzzles
+        .iter()
+        .enumerate()
+        .map(|(i, p)| (p.hash.as_str(), i))
+        .collect();
+
+    let mut result = graph
+        .execute(query(
+            "MATCH (a:Puzzle)-[r:SHARES_TECHNIQUE_PROFILE]-(b:Puzzle)
+             WHERE a.hash < b.hash
+             RETURN a.hash AS a, b.hash AS b, coalesce(r.similarity, 0.5) AS similarity",
+        ))
+        .await
+        .context("Failed to query similarity edges")?;
+
+    let mut edges = Vec::new();
+    while let Some(row) = result.next().await? {
+        let a: String = row.get("a")?;
+        let b: String = row.get("b")?;
+        if let (Some(&ia), Some(&ib)) = (index.get(a.as_str()), index.get(b.as_str())) {
+            // The galaxy never mixes variants
+            if puzzles[ia].variant == puzzles[ib].variant {
+                edges.push((ia, ib, row.get("similarity").unwrap_or(0.5)));
+            }
+        }
+    }
+    info!("Loaded {} puzzles and {} edges", puzzles.len(), edges.len());
+
+    let labels = label_propagation(puzzles.len(), &edges, max_iterations);
+    let mut groups: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+    for (i, label) in labels.into_iter().enumerate() {
+        groups.entry(label).or_default().push(i);
+    }
+
+    let run = chrono::Utc::now().to_rfc3339();
+    let mut stored = 0;
+    for members in groups.values().filter(|m| m.len() >= min_size) {
+        // Members are in hash order, so the first one names the cluster
+        let first = &puzzles[members[0]];
+        let id = format!("c-{}", &first.hash[..first.hash.len().min(12)]);
+        let summary = summarize(
+            &members
+                .iter()
+                .map(|&i| Member {
+                    se_rating: puzzles[i].se_rating,
+                    max_technique: puzzles[i].max_technique.as_deref(),
+                })
+                .collect::<Vec<_>>(),
+            DOMINANT_TECHNIQUES,
+        );
+        let placed: Vec<(f64, f64)> = members.iter().filter_map(|&i| puzzles[i].position).collect();
+        let centroid = (!placed.is_empty()).then(|| {
+            (
+                placed.iter().map(|p| p.0).sum::<f64>() / placed.len() as f64,
+                placed.iter().map(|p| p.1).sum::<f64>() / placed.len() as f64,
+            )
+        });
+        let hashes: Vec<String> = members.iter().map(|&i| puzzles[i].hash.clone()).collect();
+
+        run_query(
+            graph,
+            query(
+                "MERGE (c:Cluster {id: $id})
+                 SET c.variant = $variant, c.size = $size,
+                     c.min_se_rating = $min_se, c.max_se_rating = $max_se,
+                     c.dominant_techniques = $techniques,
+                     c.x = $x, c.y = $y,
+                     c.run = $run, c.updated_at = datetime()
+                 WITH c
+                 UNWIND $hashes AS hash
+                 MATCH (p:Puzzle {hash: hash})
+                 MERGE (p)-[m:IN_CLUSTER]->(c)
+                 SET m.run = $run",
+            )
+            .param("id", id)
+            .param("variant", first.variant.clone())
+            .param("size", summary.size as i64)
+            .param("min_se", summary.min_se_rating)
+            .param("max_se", summary.max_se_rating)
+            .param("techniques", summary.dominant_techniques)
+            .param("x", centroid.map(|c| c.0))
+            .param("y", centroid.map(|c| c.1))
+            .param("run", run.clone())
+            .param("hashes", hashes),
+        )
+        .await
+        .context("Failed to store cluster")?;
+        stored += 1;
+    }
+
+    // Drop clusters and memberships this run did not touch, including
+    // puzzles that moved to another cluster or into a community below
+    // `min_size`
+    run_query(
+        graph,
+        query(
+            "MATCH (:Puzzle)-[m:IN_CLUSTER]->(:Cluster) WHERE m.run <> $run
+             DELETE m",
+        )
+        .param("run", run.clone()),
+    )
+    .await
+    .context("Failed to remove stale cluster memberships")?;
+    run_query(
+        graph,
+        query(
+            "MATCH (c:Cluster) WHERE c.run <> $run
+             DETACH DELETE c",
+        )
+        .param("run", run),
+    )
+    .await
+    .context("Failed to remove stale clusters")?;
+
+    info!(
+        "Stored {} clusters ({} communities below {} puzzles skipped)",
+        stored,
+        groups.len() - stored,
+        min_size
+    );
+    Ok(())
+}
+
+/// Replace the `CO_OCCURS_WITH` edges between techniques with fresh counts
+/// and lift over every non-retired classic puzzle's `REQUIRES_TECHNIQUE`
+/// profile. Other variants are never analyzed, and mixing rule sets would
+/// skew lift, so the edges describe classic sudoku only.
+///
+/// Each pair gets one edge, from the alphabetically smaller name.
+async fn technique_co_occurrence(graph: &Graph, min_count: u64) -> Result<()> {
+    info!("Fetching technique profiles...");
+    let mut result = graph
+        .execute(query(
+            "MATCH (p:Puzzle {variant: 'classic'})-[:REQUIRES_TECHNIQUE]->(t:Technique)
+             WHERE p.state <> 'retired'
+             RETURN p.hash AS hash, collect(t.name) AS techniques",
+        ))
+        .await
+        .context("Failed to query technique profiles")?;
+
+    let mut profiles = Vec::new();
+    while let Some(row) = result.next().await? {
+        profiles.push(row.get::<Vec<String>>("techniques").unwrap_or_default());
+    }
+    let pairs = co_occurrence(&profiles, min_count);
+    info!(
+        "{} technique pairs co-occur in at least {} of {} puzzles",
+        pairs.len(),
+        min_count,
+        profiles.len()
+    );
+
+    let run = chrono::Utc::now().to_rfc3339();
+    for chunk in pairs.chunks(WRITE_BATCH) {
+        run_query(
+            graph,
+            query(
+                "UNWIND range(0, size($a) - 1) AS i
+                 MATCH (a:Technique {name: $a[i]})
+                 MATCH (b:Technique {name: $b[i]})
+                 MERGE (a)-[r:CO_OCCURS_WITH]->(b)
+                 SET r.count = $count[i], r.lift = $lift[i], r.run = $run",
+            )
+            .param("a", chunk.iter().map(|p| p.a.clone()).collect::<Vec<_>>())
+            .param("b", chunk.iter().map(|p| p.b.clone()).collect::<Vec<_>>())
+            .param("count", chunk.iter().map(|p| p.count as i64).collect::<Vec<_>>())
+            .param("lift", chunk.iter().map(|p| p.lift).collect::<Vec<_>>())
+            .param("run", run.clone()),
+        )
+        .await
+        .context("Failed to store CO_OCCURS_WITH edges")?;
+    }
+
+    run_query(
+        graph,
+        query(
+            "MATCH (:Technique)-[r:CO_OCCURS_WITH]->(:Technique)
+             WHERE r.run IS NULL OR r.run <> $run
+             DELETE r",
+        )
+        .param("run", run),
+    )
+    .await
+    .context("Failed to remove stale CO_OCCURS_WITH edges")?;
+
+    info!("Stored {} CO_OCCURS_WITH edges", pairs.len());
+    Ok(())
+}
+
+/// One `REQUIRES_TECHNIQUE` edge as collected by the export query. Name
+/// and count travel together so a missing count cannot shift the others.
+#[derive(serde::Deserialize)]
+struct TechniqueCount {
+    name: String,
+    count: i64,
+}
+
+/// Stream analyzed puzzles with their technique profiles and play aggregates.
+async fn export(
+    graph: &Graph,
+    format: ExportFormat,
+    output: Option<PathBuf>,
+    tier: Option<&str>,
+    technique: Option<&str>,
+) -> Result<()> {
+    let mut cypher = String::from(
+        "MATCH (p:Puzzle)
+         WHERE p.max_se_rating IS NOT NULL",
+    );
+    if technique.is_some() {
+        cypher.push_str(
+            "
+           AND EXISTS {
+             MATCH (p)-[:REQUIRES_TECHNIQUE]->(ft:Technique)
+             WHERE ft.name = $technique OR ft.display_name = $technique
+           }",
+        );
+    }
+    cypher.push_str(
+        "
+         OPTIONAL MATCH (p)-[:IN_TIER]->(d:DifficultyTier)",
+    );
+    if tier.is_some() {
+        cypher.push_str(
+            "
+         WITH p, d WHERE d.name = $tier",
+        );
+    }
+    cypher.push_str(
+        "
+         OPTIONAL MATCH (p)-[r:REQUIRES_TECHNIQUE]->(t:Technique)
+         WITH p, d, collect(CASE WHEN t IS NULL THEN null
+                              ELSE {name: t.name, count: COALESCE(r.count, 0)} END) AS techniques
+         RETURN p.hash AS puzzle_hash, p.puzzle_string AS puzzle_string,
+                p.short_code AS short_code, d.name AS tier,
+                p.max_technique AS max_technique, p.max_se_rating AS max_se_rating,
+                techniques,
+                COALESCE(p.play_count, 0) AS play_count,
+                COALESCE(p.win_count, 0) AS win_count,
+                CASE WHEN p.win_count > 0
+                     THEN toFloat(p.total_solve_time) / p.win_count
+                     ELSE 0.0 END AS avg_solve_time,
+                CASE WHEN p.play_count > 0
+                     THEN toFloat(p.win_count) / p.play_count
+                     ELSE 0.0 END AS win_rate",
+    );
+
+    let mut q = query(&cypher);
+    if let Some(tier) = tier {
+        q = q.param("tier", tier);
+    }
+    if let Some(technique) = technique {
+        q = q.param("technique", technique);
+    }
+
+    let sink: Box<dyn Write> = match &output {
+        Some(path) => Box::new(
+            File::create(path)
+                .with_context(|| format!("Failed to create {}", path.display()))?,
+        ),
+        None => Box::new(io::stdout().lock()),
+    };
+    let mut out = BufWriter::new(sink);
+
+    if let ExportFormat::Csv = format {
+        writeln!(out, "{}", CSV_HEADER.join(","))?;
+    }
+
+    let mut result = graph.execute(q).await.context("Failed to query puzzles")?;
+    let mut count = 0usize;
+    while let Some(row) = result.next().await? {
+        let techniques: BTreeMap<String, i64> = row
+            .get::<Vec<TechniqueCount>>("techniques")
+            .unwrap_or_default()
+            .into_iter()
+            .map(|t| (t.name, t.count))
+            .collect();
+
+        let export_row = ExportRow {
+            puzzle_hash: row.get("puzzle_hash")?,
+            puzzle_string: row.get("puzzle_string").unwrap_or_default(),
+            short_code: row
+                .get::<String>("short_code")
+                .ok()
+                .filter(|s| !s.is_empty()),
+            tier: row.get("tier").ok(),
+            max_technique: row.get("max_technique").ok(),
+            max_se_rating: row.get("max_se_rating").unwrap_or(0.0),
+            techniques,
+            play_count: row.get("play_count").unwrap_or(0),
+            win_count: row.get("win_count").unwrap_or(0),
+            avg_solve_time: row.get("avg_solve_time").unwrap_or(0.0),
+            win_rate: row.get("win_rate").unwrap_or(0.0),
+        };
+
+        match format {
+            ExportFormat::Jsonl => {
+                serde_json::to_writer(&mut out, &export_row)?;
+                writeln!(out)?;
+            }
+            ExportFormat::Csv => writeln!(out, "{}", export_row.to_csv_record())?,
+        }
+        count += 1;
+    }
+    out.flush()?;
+
+    info!("Exported {} puzzles", count);
+    Ok(())
+}
@@ -0,0 +1,78 @@
+//! Compact encoding of a puzzle's ordered solving path.
+//!
+//! The path is stored on the puzzle node as a single string property,
+//! `p.solve_path`, with one step per `;`-separated entry:
+//!
+//! ```text
+//! <Technique>:<cell><op><digits>
+//! ```
+//!
+//! `Technique` is the stable enum name (as in `Technique.name`), `cell` is the
+//! row-major index 0..=80, `op` is `=` for a placement or `-` for candidate
+//! eliminations, and `digits` are the placed value or eliminated candidates.
+//! For example `HiddenSingle:40=5;XWing:12-37` places a 5 in r5c5, then
+//! removes candidates 3 and 7 from r2c4. A typical path is 1-2 KB.
+
+use std::fmt;
+
+/// What a single solving step did to its cell.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StepAction {
+    Place(u8),
+    Eliminate(Vec<u8>),
+}
+
+/// One step of the logical solution, in the order the solver found it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SolveStep {
+    /// Stable technique enum name, e.g. "HiddenSingle"
+    pub technique: &'static str,
+    /// Row-major cell index (0..=80)
+    pub cell: u8,
+    pub action: StepAction,
+}
+
+impl fmt::Display for SolveStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.technique, self.cell)?;
+        match &self.action {
+            StepAction::Place(value) => write!(f, "={value}"),
+            StepAction::Eliminate(values) => {
+                f.write_str("-")?;
+                values.iter().try_for_each(|v| write!(f, "{v}"))
+            }
+        }
+    }
+}
+
+/// Encode a full path for storage in `p.solve_path`.
+pub fn encode_path(steps: &[SolveStep]) -> String {
+    steps
+        .iter()
+        .map(|s| s.to_string())
+        .collect::<Vec<_>>()
+        .join(";")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_path() {
+        let steps = vec![
+            SolveStep {
+                technique: "HiddenSingle",
+                cell: 40,
+                action: StepAction::Place(5),
+            },
+            SolveStep {
+                technique: "XWing",
+                cell: 12,
+                action: StepAction::Eliminate(vec![3, 7]),
+            },
+        ];
+        assert_eq!(encode_path(&steps), "HiddenSingle:40=5;XWing:12-37");
+        assert_eq!(encode_path(&[]), "");
+    }
+}
@@ -0,0 +1,213 @@
+use axum::http::header::{
+    CACHE_CONTROL, CONTENT_TYPE, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED, VARY,
+};
+use axum::http::{HeaderMap, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::error::ApiError;
+use crate::packed::{self, PackedGalaxy};
+
+pub const PUZZLE_CACHE_CONTROL: &str = "public, max-age=60, stale-while-revalidate=300";
+pub const TECHNIQUES_CACHE_CONTROL: &str = "public, max-age=300, stale-while-revalidate=3600";
+pub const OVERVIEW_CACHE_CONTROL: &str = "public, max-age=60, stale-while-revalidate=120";
+pub const STATS_CACHE_CONTROL: &str = "public, max-age=30, stale-while-revalidate=60";
+/// Revalidate every time; the ETag still saves the transfer.
+pub const REVALIDATE_CACHE_CONTROL: &str = "no-cache";
+
+/// Serialize `body` as JSON with a content-derived ETag, answering
+/// `304 Not Modified` when the request's validators still match.
+///
+/// ETags are weak because the compression layer re-encodes the body.
+/// `last_modified` is only used when the client sent no `If-None-Match`.
+pub fn json_response<T: Serialize>(
+    request_headers: &HeaderMap,
+    body: &T,
+    cache_control: &'static str,
+    last_modified: Option<DateTime<Utc>>,
+) -> Result<Response, ApiError> {
+    let bytes = serde_json::to_vec(body).map_err(|e| ApiError::Internal(e.to_string()))?;
+    Ok(bytes_response(
+        request_headers,
+        bytes,
+        "application/json",
+        cache_control,
+        last_modified,
+        false,
+    ))
+}
+
+/// Like [`json_response`] for galaxy payloads, but answers with the packed
+/// MessagePack encoding when the client's `Accept` header asks for it.
+///
+/// Both representations get `Vary: Accept` and their own ETag, so shared
+/// caches never hand one to a client that asked for the other.
+pub fn negotiated_response<T: Serialize>(
+    request_headers: &HeaderMap,
+    body: &T,
+    pack: impl FnOnce(&T) -> PackedGalaxy,
+    cache_control: &'static str,
+) -> Result<Response, ApiError> {
+    let (bytes, content_type) = if packed::wants_msgpack(request_headers) {
+        let bytes = pack(body)
+            .to_msgpack()
+            .map_err(|e| ApiError::Internal(e.to_string()))?;
+        (bytes, packed::MSGPACK)
+    } else {
+        let bytes = serde_json::to_vec(body).map_err(|e| ApiError::Internal(e.to_string()))?;
+        (bytes, "application/json")
+    };
+    Ok(bytes_response(
+        request_headers,
+        bytes,
+        content_type,
+        cache_control,
+        None,
+        true,
+    ))
+}
+
+fn bytes_response(
+    request_headers: &HeaderMap,
+    bytes: Vec<u8>,
+    content_type: &'static str,
+    cache_control: &'static str,
+    last_modified: Option<DateTime<Utc>>,
+    vary_accept: bool,
+) -> Response {
+    let etag = weak_etag(&bytes);
+    let last_modified = last_modified.map(http_date);
+
+    let not_modified = match request_headers.get(IF_NONE_MATCH) {
+        Some(v) => v.to_str().is_ok_and(|v| etag_matches(v, &etag)),
+        None => match (&last_modified, request_headers.get(IF_MODIFIED_SINCE)) {
+            (Some(lm), Some(ims)) => ims.to_str().is_ok_and(|ims| not_modified_since(lm, ims)),
+            _ => false,
+        },
+    };
+
+    let mut headers = HeaderMap::new();
+    headers.insert(CACHE_CONTROL, HeaderValue::from_static(cache_control));
+    if vary_accept {
+        headers.insert(VARY, HeaderValue::from_static("accept"));
+    }
+    if let Ok(v) = HeaderValue::from_str(&etag) {
+        headers.insert(ETAG, v);
+    }
+    if let Some(v) = last_modified.and_then(|lm| HeaderValue::from_str(&lm).ok()) {
+        headers.insert(LAST_MODIFIED, v);
+    }
+
+    if not_modified {
+        return (StatusCode::NOT_MODIFIED, headers).into_response();
+    }
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static(content_type));
+    (StatusCode::OK, headers, bytes).into_response()
+}
+
+/// `W/"<fnv1a-64 of body>"`
+fn weak_etag(bytes: &[u8]) -> String {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in bytes {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    format!("W/\"{:016x}\"", hash)
+}
+
+/// Weak comparison of an `If-None-Match` list against our ETag.
+fn etag_matches(if_none_match: &str, etag: &str) -> bool {
+    let opaque = |tag: &str| tag.trim().trim_start_matches("W/").to_string();
+    let ours = opaque(etag);
+    if_none_match
+        .split(',')
+        .any(|candidate| candidate.trim() == "*" || opaque(candidate) == ours)
+}
+
+fn not_modified_since(last_modified: &str, if_modified_since: &str) -> bool {
+    match (
+        DateTime::parse_from_rfc2822(last_modified),
+        DateTime::parse_from_rfc2822(if_modified_since),
+    ) {
+        (Ok(lm), Ok(ims)) => lm <= ims,
+        _ => false,
+    }
+}
+
+/// IMF-fixdate, e.g. `Sun, 06 Nov 1994 08:49:37 GMT`.
+fn http_date(ts: DateTime<Utc>) -> String {
+    ts.format("%a, %d %b %Y %H:%M:%S GMT").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_etag_matches_weak_and_list() {
+        let etag = weak_etag(b"{\"a\":1}");
+        assert!(etag_matches(&etag, &etag));
+        assert!(etag_matches(etag.trim_start_matches("W/"), &etag));
+        assert!(etag_matches(&format!("\"other\", {etag}"), &etag));
+        assert!(etag_matches("*", &etag));
+        assert!(!etag_matches("W/\"0000000000000000\"", &etag));
+    }
+
+    #[test]
+    fn test_json_response_not_modified() {
+        let body = serde_json::json!({ "hello": "world" });
+        let first = json_response(&HeaderMap::new(), &body, PUZZLE_CACHE_CONTROL, None).unwrap();
+        assert_eq!(first.status(), StatusCode::OK);
+        let etag = first.headers().get(ETAG).unwrap().clone();
+
+        let mut headers = HeaderMap::new();
+        headers.insert(IF_NONE_MATCH, etag);
+        let second = json_response(&headers, &body, PUZZLE_CACHE_CONTROL, None).unwrap();
+        assert_eq!(second.status(), StatusCode::NOT_MODIFIED);
+        assert!(second.headers().contains_key(CACHE_CONTROL));
+    }
+
+    #[test]
+    fn test_negotiated_response_content_type() {
+        let body: Vec<crate::models::galaxy::GalaxyNode> = Vec::new();
+        let json = negotiated_response(
+            &HeaderMap::new(),
+            &body,
+            |nodes| PackedGalaxy::from_nodes(nodes),
+            REVALIDATE_CACHE_CONTROL,
+        )
+        .unwrap();
+        assert_eq!(json.headers()[CONTENT_TYPE], "application/json");
+        assert_eq!(json.headers()[VARY], "accept");
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            axum::http::header::ACCEPT,
+            HeaderValue::from_static(packed::MSGPACK),
+        );
+        let msgpack = negotiated_response(
+            &headers,
+            &body,
+            |nodes| PackedGalaxy::from_nodes(nodes),
+            REVALIDATE_CACHE_CONTROL,
+        )
+        .unwrap();
+        assert_eq!(msgpack.headers()[CONTENT_TYPE], packed::MSGPACK);
+        assert_ne!(json.headers()[ETAG], msgpack.headers()[ETAG]);
+    }
+
+    #[test]
+    fn test_if_modified_since() {
+        let lm = DateTime::from_timestamp(784111777, 0).unwrap();
+        assert_eq!(http_date(lm), "Sun, 06 Nov 1994 08:49:37 GMT");
+        assert!(not_modified_since(
+            &http_date(lm),
+            "Sun, 06 Nov 1994 08:49:37 GMT"
+        ));
+        assert!(!not_modified_since(
+            &http_date(lm),
+            "Sat, 05 Nov 1994 08:49:37 GMT"
+        ));
+    }
+}
@@ -10,6 +10,7 @@ pub struct Config {
     pub port: u16,
     pub base_url: String,
     pub mining_api_key: Option<String>,
+    pub admin_api_key: Option<String>,
 }
 
 impl Config {
@@ -26,6 +27,7 @@ impl Config {
                 .unwrap_or(3000),
             base_url: env::var("BASE_URL").unwrap_or_else(|_| "http://localhost:3000".into()),
             mining_api_key: env::var("MINING_API_KEY").ok(),
+            admin_api_key: env::var("ADMIN_API_KEY").ok(),
         }
     }
 
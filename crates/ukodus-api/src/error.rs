@@ -1,8 +1,25 @@
 use axum::http::StatusCode;
 use axum::response::{IntoResponse, Response};
 use axum::Json;
+use serde::Serialize;
 use serde_json::json;
 
+/// A problem with one field of a request body.
+#[derive(Debug, Serialize)]
+pub struct FieldError {
+    pub field: &'static str,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: &'static str, message: impl ToString) -> Self {
+        Self {
+            field,
+            message: message.to_string(),
+        }
+    }
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum ApiError {
     #[error("not found: {0}")]
@@ -11,9 +28,15 @@ pub enum ApiError {
     #[error("bad request: {0}")]
     BadRequest(String),
 
+    #[error("invalid input: {}", describe_fields(.0))]
+    Validation(Vec<FieldError>),
+
     #[error("unauthorized: {0}")]
     Unauthorized(String),
 
+    #[error("conflict: {0}")]
+    Conflict(String),
+
     #[error("service unavailable: {0}")]
     ServiceUnavailable(String),
 
@@ -27,12 +50,34 @@ pub enum ApiError {
     Redis(#[from] redis::RedisError),
 }
 
+impl ApiError {
+    /// A 400 for a single bad field.
+    pub fn invalid(field: &'static str, message: impl ToString) -> Self {
+        ApiError::Validation(vec![FieldError::new(field, message)])
+    }
+}
+
+fn describe_fields(errors: &[FieldError]) -> String {
+    errors
+        .iter()
+        .map(|e| format!("{}: {}", e.field, e.message))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
 impl IntoResponse for ApiError {
     fn into_response(self) -> Response {
+        if let ApiError::Validation(errors) = &self {
+            let body = json!({ "error": describe_fields(errors), "fields": errors });
+            return (StatusCode::BAD_REQUEST, Json(body)).into_response();
+        }
+
         let (status, message) = match &self {
             ApiError::NotFound(msg) => (StatusCode::NOT_FOUND, msg.clone()),
             ApiError::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg.clone()),
+            ApiError::Validation(errors) => (StatusCode::BAD_REQUEST, describe_fields(errors)),
             ApiError::Unauthorized(msg) => (StatusCode::UNAUTHORIZED, msg.clone()),
+            ApiError::Conflict(msg) => (StatusCode::CONFLICT, msg.clone()),
             ApiError::ServiceUnavailable(msg) => (StatusCode::SERVICE_UNAVAILABLE, msg.clone()),
             ApiError::Internal(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg.clone()),
             ApiError::Neo4j(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
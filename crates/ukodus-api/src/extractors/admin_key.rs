@@ -0,0 +1,34 @@
+use std::sync::Arc;
+
+use axum::extract::FromRequestParts;
+use axum::http::request::Parts;
+
+use crate::error::ApiError;
+use crate::state::AppState;
+
+pub struct AdminAuth;
+
+impl FromRequestParts<Arc<AppState>> for AdminAuth {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &Arc<AppState>,
+    ) -> Result<Self, Self::Rejection> {
+        let expected = state.config.admin_api_key.as_deref().ok_or_else(|| {
+            ApiError::ServiceUnavailable("moderation not configured".into())
+        })?;
+
+        let provided = parts
+            .headers
+            .get("X-Admin-Key")
+            .and_then(|v| v.to_str().ok())
+            .ok_or_else(|| ApiError::Unauthorized("missing X-Admin-Key header".into()))?;
+
+        if provided != expected {
+            return Err(ApiError::Unauthorized("invalid admin key".into()));
+        }
+
+        Ok(AdminAuth)
+    }
+}
@@ -1,2 +1,4 @@
+mod admin_key;
 mod api_key;
+pub use admin_key::AdminAuth;
 pub use api_key::ApiKeyAuth;
@@ -1,41 +1,123 @@
+use chrono::{DateTime, Utc};
 use neo4rs::{query, Graph};
+use ukodus_grid::Variant;
 use uuid::Uuid;
 
 use crate::error::ApiError;
 use crate::models::galaxy::{
-    GalaxyEdge, GalaxyNode, GalaxyOverview, GalaxyStats, ShareDetail, ShareInput, ShareResponse,
+    Community, CommunityDetail, GalaxyEdge, GalaxyNode, GalaxyOverview, GalaxyStats,
+    LearningStep, OverviewParams, OverviewSort, ShareDetail, ShareInput, ShareResponse,
+    TimelineBucket, ViewportQuery,
 };
-use crate::models::puzzle::{LeaderboardEntry, PoolCount, PoolStats, PuzzleDetail, TechniqueInfo};
+use crate::models::lifecycle::{LifecycleCount, LifecyclePuzzle, PuzzleState};
+use crate::models::puzzle::{
+    DiscovererEntry, GameReplay, LeaderboardEntry, MinedPuzzleInput, PoolCount, PoolStats, PuzzleDetail, SolveTimeDistribution,
+    TechniqueInfo,
+};
+use crate::models::report::{ReportDetail, RetirePuzzleResponse};
+use crate::models::technique::{
+    Prerequisite, PrerequisiteEdge, PrerequisiteGraph, TechniqueEdge, TechniqueGraph, TechniqueNode,
+    TechniquePrerequisites,
+};
+use crate::services::move_log;
+use crate::services::result_service::NoteActivity;
+use crate::services::short_code;
+use crate::services::solve_times;
+use crate::services::solve_path::TechniqueLookup;
+use crate::services::viewport::CellRow;
 
 // ── Puzzle CRUD ──────────────────────────────────────────────────────
 
+/// Identity hash for a puzzle first stored now. Classic hashes predate
+/// variants and cover the grid alone; other variants prefix their name so
+/// the same grid under different rules is a different puzzle.
+pub fn puzzle_hash(variant: Variant, canonical: &str) -> String {
+    match variant {
+        Variant::Classic => format!("{:x}", md5_hash(canonical)),
+        variant => format!("{:x}", md5_hash(&format!("{}:{}", variant.as_str(), canonical))),
+    }
+}
+
+/// The hash a canonical grid is stored under: an existing puzzle with the
+/// same grid and variant keeps its hash (older ones carry client-computed
+/// hashes), anything else gets [`puzzle_hash`]. Client-supplied hashes are
+/// never used as identity.
+///
+/// Mined puzzles stored before grids were canonicalized may still hold the
+/// `.` form (see migration 014), so that spelling matches too.
+pub async fn resolve_puzzle_hash(
+    graph: &Graph,
+    canonical: &str,
+    variant: Variant,
+) -> Result<String, ApiError> {
+    let q = query(
+        "MATCH (p:Puzzle {variant: $variant})
+         WHERE p.puzzle_string IN [$ps, $legacy]
+         RETURN p.hash AS hash
+         ORDER BY p.created_at ASC
+         LIMIT 1",
+    )
+    .param("ps", canonical)
+    .param("legacy", canonical.replace('0', "."))
+    .param("variant", variant.as_str());
+
+    let mut result = graph.execute(q).await?;
+    if let Some(row) = result.next().await? {
+        if let Ok(hash) = row.get::<String>("hash") {
+            return Ok(hash);
+        }
+    }
+    Ok(puzzle_hash(variant, canonical))
+}
+
+/// Store a player-submitted puzzle, returning its hash (see
+/// [`resolve_puzzle_hash`]) and whether it is new.
+///
+/// `client_code` is the WASM-generated code, kept for legacy `?s=` links.
+/// The server-side `short_code` is allocated separately by
+/// [`ensure_puzzle_short_code`].
 pub async fn upsert_puzzle(
     graph: &Graph,
-    puzzle_hash: &str,
     puzzle_string: &str,
-    short_code: Option<&str>,
+    variant: Variant,
+    client_code: Option<&str>,
     difficulty: &str,
     se_rating: f32,
-) -> Result<bool, ApiError> {
+) -> Result<(String, bool), ApiError> {
+    let puzzle_hash = resolve_puzzle_hash(graph, puzzle_string, variant).await?;
     let q = query(
         "MERGE (p:Puzzle {hash: $hash})
-         ON CREATE SET p.puzzle_string = $ps, p.short_code = $sc, p.difficulty = $diff,
+         ON CREATE SET p.puzzle_string = $ps, p.variant = $variant,
+                       p.client_code = $cc, p.difficulty = $diff,
                        p.se_rating = $rating, p.play_count = 0, p.total_solve_time = 0,
-                       p.win_count = 0, p.needs_analysis = true, p.created_at = datetime()
-         ON MATCH SET  p.short_code = COALESCE($sc, p.short_code)
-         RETURN p.play_count = 0 AS is_new",
+                       p.win_count = 0, p.source = 'player', p.state = 'discovered',
+                       p.state_changed_at = datetime(), p.created_at = datetime(),
+                       p.updated_at = datetime()
+         ON MATCH SET  p.client_code = COALESCE(p.client_code, $cc)
+         RETURN p.play_count = 0 AS is_new, p.variant AS variant",
     )
-    .param("hash", puzzle_hash)
+    .param("hash", puzzle_hash.as_str())
     .param("ps", puzzle_string)
-    .param("sc", short_code.unwrap_or(""))
+    .param("variant", variant.as_str())
+    .param("cc", client_code.filter(|c| !c.is_empty()))
     .param("diff", difficulty)
     .param("rating", se_rating as f64);
 
     let mut result = graph.execute(q).await?;
     if let Some(row) = result.next().await? {
-        Ok(row.get::<bool>("is_new").unwrap_or(true))
+        let stored = row_variant(&row);
+        if stored != variant {
+            return Err(ApiError::Conflict(format!(
+                "puzzle {} is a {} puzzle, not {}",
+                puzzle_hash,
+                stored.as_str(),
+                variant.as_str()
+            )));
+        }
+        let is_new = row.get::<bool>("is_new").unwrap_or(true);
+        Ok((puzzle_hash, is_new))
     } else {
-        Ok(true)
+        Ok((puzzle_hash, true))
     }
 }
 
@@ -44,6 +126,7 @@ pub async fn create_game_result(
     puzzle_hash: &str,
     input: &crate::models::puzzle::GameResultInput,
     verified: bool,
+    notes: Option<&NoteActivity>,
 ) -> Result<String, ApiError> {
     let id = Uuid::new_v4().to_string();
     let q = query(
@@ -58,6 +141,7 @@ pub async fn create_game_result(
              avg_move_time_ms: $avg_mt,
              min_move_time_ms: $min_mt,
              move_time_std_dev: $std_dev,
+             variant: $variant,
              player_id: $player,
              player_tag: $tag,
              platform: $platform,
@@ -65,6 +149,11 @@ pub async fn create_game_result(
              os_version: $os_version,
              app_version: $app_version,
              verified: $verified,
+             notes_added: $notes_added,
+             notes_removed: $notes_removed,
+             auto_notes: $auto_notes,
+             noted_placement_ratio: $noted_ratio,
+             move_log: $move_log,
              created_at: datetime()
          })
          CREATE (r)-[:FOR_PUZZLE]->(p)
@@ -80,13 +169,27 @@ pub async fn create_game_result(
     .param("avg_mt", input.avg_move_time_ms.unwrap_or(0) as i64)
     .param("min_mt", input.min_move_time_ms.unwrap_or(0) as i64)
     .param("std_dev", input.move_time_std_dev.unwrap_or(0.0) as f64)
+    .param("variant", input.variant.as_str())
     .param("player", input.player_id.as_str())
     .param("tag", input.player_tag.as_deref().unwrap_or(""))
     .param("platform", input.platform.as_deref().unwrap_or("web"))
     .param("device_model", input.device_model.as_deref().unwrap_or(""))
     .param("os_version", input.os_version.as_deref().unwrap_or(""))
     .param("app_version", input.app_version.as_deref().unwrap_or(""))
-    .param("verified", verified);
+    .param("verified", verified)
+    // Replay summary; null when the client sent no move log
+    .param("notes_added", notes.map(|n| n.notes_added as i64))
+    .param("notes_removed", notes.map(|n| n.notes_removed as i64))
+    .param("auto_notes", notes.map(|n| n.auto_notes as i64))
+    .param("noted_ratio", notes.map(|n| n.noted_placement_ratio as f64))
+    .param(
+        "move_log",
+        input
+            .move_log
+            .as_deref()
+            .filter(|log| !log.is_empty())
+            .map(move_log::encode),
+    );
 
     let mut result = graph.execute(q).await?;
     if let Some(row) = result.next().await? {
@@ -96,6 +199,66 @@ pub async fn create_game_result(
     }
 }
 
+/// A stored result with its puzzle, and the encoded move log if one was sent.
+/// `moves` is left empty for the caller to fill from the log.
+pub async fn get_game_replay(
+    graph: &Graph,
+    result_id: &str,
+) -> Result<Option<(GameReplay, Option<String>)>, ApiError> {
+    let q = query(
+        "MATCH (r:GameResult {id: $id})-[:FOR_PUZZLE]->(p:Puzzle)
+         RETURN r.id AS id, p.hash AS puzzle_hash, p.puzzle_string AS puzzle_string,
+                p.variant AS variant, r.result AS result, r.time_secs AS time_secs, r.verified AS verified,
+                r.move_log AS move_log",
+    )
+    .param("id", result_id);
+
+    let mut result = graph.execute(q).await?;
+    let Some(row) = result.next().await? else {
+        return Ok(None);
+    };
+    let replay = GameReplay {
+        result_id: row.get("id").unwrap_or_default(),
+        puzzle_hash: row.get("puzzle_hash").unwrap_or_default(),
+        puzzle_string: row.get("puzzle_string").unwrap_or_default(),
+        variant: row_variant(&row),
+        result: row.get("result").unwrap_or_default(),
+        time_secs: row.get::<i64>("time_secs").unwrap_or(0) as u64,
+        verified: row.get("verified").unwrap_or(false),
+        moves: Vec::new(),
+    };
+    Ok(Some((replay, row.get("move_log").ok())))
+}
+
+/// Puzzle string, variant and the most recent encoded move logs for a puzzle,
+/// from verified results only so bot submissions don't skew the heatmap.
+pub async fn get_puzzle_move_logs(
+    graph: &Graph,
+    hash: &str,
+    limit: u64,
+) -> Result<Option<(String, Variant, Vec<String>)>, ApiError> {
+    let q = query(
+        "MATCH (p:Puzzle {hash: $hash})
+         OPTIONAL MATCH (r:GameResult)-[:FOR_PUZZLE]->(p)
+         WHERE r.move_log IS NOT NULL AND r.verified = true
+         WITH p, r ORDER BY r.created_at DESC
+         WITH p, collect(r.move_log)[..$limit] AS logs
+         RETURN p.puzzle_string AS puzzle_string, p.variant AS variant, logs",
+    )
+    .param("hash", hash)
+    .param("limit", limit as i64);
+
+    let mut result = graph.execute(q).await?;
+    let Some(row) = result.next().await? else {
+        return Ok(None);
+    };
+    Ok(Some((
+        row.get("puzzle_string").unwrap_or_default(),
+        row_variant(&row),
+        row.get("logs").unwrap_or_default(),
+    )))
+}
+
 pub async fn update_puzzle_aggregates(graph: &Graph, puzzle_hash: &str) -> Result<(), ApiError> {
     let q = query(
         "MATCH (p:Puzzle {hash: $hash})
@@ -105,14 +268,93 @@ pub async fn update_puzzle_aggregates(graph: &Graph, puzzle_hash: &str) -> Resul
               sum(CASE WHEN r.result = 'Win' THEN 1 ELSE 0 END) AS wins
          SET p.play_count = plays,
              p.total_solve_time = total_time,
-             p.win_count = wins",
+             p.win_count = wins,
+             p.updated_at = datetime()",
     )
     .param("hash", puzzle_hash);
 
+    graph.run(q).await?;
+
+    // Verified-win distribution, bucketed by solve_times::BUCKET_EDGES_SECS
+    let q = query(
+        "MATCH (p:Puzzle {hash: $hash})
+         OPTIONAL MATCH (r:GameResult)-[:FOR_PUZZLE]->(p)
+         WHERE r.result = 'Win' AND r.verified = true
+         WITH p, collect(r.time_secs) AS times,
+              percentileCont(r.time_secs, 0.1) AS p10,
+              percentileCont(r.time_secs, 0.5) AS p50,
+              percentileCont(r.time_secs, 0.9) AS p90
+         SET p.verified_win_count = size(times),
+             p.solve_time_p10 = p10,
+             p.solve_time_p50 = p50,
+             p.solve_time_p90 = p90,
+             p.solve_time_hist = [i IN range(0, size($edges)) |
+                 size([t IN times WHERE (i = 0 OR t >= $edges[i - 1])
+                                    AND (i = size($edges) OR t < $edges[i])])]",
+    )
+    .param("hash", puzzle_hash)
+    .param("edges", solve_times::BUCKET_EDGES_SECS.to_vec());
+
     graph.run(q).await?;
     Ok(())
 }
 
+/// How result `result_id` ranks against the other verified wins on its
+/// puzzle, as (slower, ties, others).
+pub async fn get_solve_time_rank(
+    graph: &Graph,
+    puzzle_hash: &str,
+    result_id: &str,
+    time_secs: u64,
+) -> Result<(u64, u64, u64), ApiError> {
+    let q = query(
+        "MATCH (r:GameResult)-[:FOR_PUZZLE]->(:Puzzle {hash: $hash})
+         WHERE r.result = 'Win' AND r.verified = true AND r.id <> $id
+         RETURN sum(CASE WHEN r.time_secs > $time THEN 1 ELSE 0 END) AS slower,
+                sum(CASE WHEN r.time_secs = $time THEN 1 ELSE 0 END) AS ties,
+                count(r) AS others",
+    )
+    .param("hash", puzzle_hash)
+    .param("id", result_id)
+    .param("time", time_secs as i64);
+
+    let mut result = graph.execute(q).await?;
+    if let Some(row) = result.next().await? {
+        let get = |key: &str| row.get::<i64>(key).unwrap_or(0).max(0) as u64;
+        Ok((get("slower"), get("ties"), get("others")))
+    } else {
+        Ok((0, 0, 0))
+    }
+}
+
+pub async fn get_solve_time_distribution(
+    graph: &Graph,
+    hash: &str,
+) -> Result<Option<SolveTimeDistribution>, ApiError> {
+    let q = query(
+        "MATCH (p:Puzzle {hash: $hash})
+         RETURN p.hash AS puzzle_hash,
+                COALESCE(p.verified_win_count, 0) AS samples,
+                p.solve_time_p10 AS p10, p.solve_time_p50 AS p50, p.solve_time_p90 AS p90,
+                COALESCE(p.solve_time_hist, []) AS hist",
+    )
+    .param("hash", hash);
+
+    let mut result = graph.execute(q).await?;
+    let Some(row) = result.next().await? else {
+        return Ok(None);
+    };
+    let hist: Vec<i64> = row.get("hist").unwrap_or_default();
+    Ok(Some(SolveTimeDistribution {
+        puzzle_hash: row.get("puzzle_hash").unwrap_or_default(),
+        samples: row.get::<i64>("samples").unwrap_or(0) as u64,
+        p10_secs: row.get("p10").ok(),
+        p50_secs: row.get("p50").ok(),
+        p90_secs: row.get("p90").ok(),
+        buckets: solve_times::buckets(&hist),
+    }))
+}
+
 pub async fn get_puzzle_play_count(graph: &Graph, puzzle_hash: &str) -> Result<u64, ApiError> {
     let q = query(
         "MATCH (p:Puzzle {hash: $hash}) RETURN p.play_count AS count",
@@ -127,26 +369,49 @@ pub async fn get_puzzle_play_count(graph: &Graph, puzzle_hash: &str) -> Result<u
     }
 }
 
-pub async fn get_puzzle_by_hash(
-    graph: &Graph,
-    hash: &str,
-) -> Result<Option<PuzzleDetail>, ApiError> {
-    let q = query(
-        "MATCH (p:Puzzle {hash: $hash})
-         OPTIONAL MATCH (p)-[:REQUIRES_TECHNIQUE]->(t:Technique)
+/// Techniques, stats, empirical difficulty and discoverer for `p`: the
+/// [`PuzzleDetail`] tail shared by the hash, code and random lookups.
+const PUZZLE_DETAIL_RETURN: &str = "OPTIONAL MATCH (p)-[:REQUIRES_TECHNIQUE]->(t:Technique)
          WITH p, t ORDER BY t.se_rating ASC
          WITH p, collect(t.name) AS techs
          RETURN p.hash AS puzzle_hash, p.puzzle_string AS puzzle_string,
                 p.short_code AS short_code, p.difficulty AS difficulty,
-                p.se_rating AS se_rating, p.play_count AS play_count,
+                p.se_rating AS se_rating, p.max_se_rating IS NOT NULL AS rating_verified,
+                p.state AS state, p.variant AS variant,
+                p.play_count AS play_count,
                 CASE WHEN p.win_count > 0
                      THEN toFloat(p.total_solve_time) / p.win_count
                      ELSE 0.0 END AS avg_solve_time,
                 CASE WHEN p.play_count > 0
                      THEN toFloat(p.win_count) / p.play_count
                      ELSE 0.0 END AS win_rate,
-                techs",
-    )
+                techs,
+                CASE WHEN p.empirical_samples IS NULL THEN null ELSE {
+                    samples: p.empirical_samples,
+                    p10_solve_time: p.empirical_p10_time,
+                    median_solve_time: p.empirical_median_time,
+                    p90_solve_time: p.empirical_p90_time,
+                    win_rate: p.empirical_win_rate,
+                    mistake_rate: p.empirical_mistake_rate,
+                    normalized_solve_time: p.empirical_normalized_time,
+                    tier: p.empirical_tier,
+                    tier_mismatch: COALESCE(p.tier_mismatch, false)
+                } END AS empirical,
+                head([(r:GameResult)-[d:DISCOVERED]->(p) | {
+                    player_id: r.player_id,
+                    player_tag: CASE WHEN r.player_tag = '' THEN null ELSE r.player_tag END,
+                    discovered_at: toString(d.discovered_at)
+                }]) AS discoverer,
+                COALESCE(p.updated_at, p.created_at).epochMillis AS updated_at_ms";
+
+pub async fn get_puzzle_by_hash(
+    graph: &Graph,
+    hash: &str,
+) -> Result<Option<PuzzleDetail>, ApiError> {
+    let q = query(&format!(
+        "MATCH (p:Puzzle {{hash: $hash}})
+         {PUZZLE_DETAIL_RETURN}"
+    ))
     .param("hash", hash);
 
     let mut result = graph.execute(q).await?;
@@ -161,23 +426,15 @@ pub async fn get_puzzle_by_code(
     graph: &Graph,
     code: &str,
 ) -> Result<Option<PuzzleDetail>, ApiError> {
-    let q = query(
-        "MATCH (p:Puzzle {short_code: $code})
-         OPTIONAL MATCH (p)-[:REQUIRES_TECHNIQUE]->(t:Technique)
-         WITH p, t ORDER BY t.se_rating ASC
-         WITH p, collect(t.name) AS techs
-         RETURN p.hash AS puzzle_hash, p.puzzle_string AS puzzle_string,
-                p.short_code AS short_code, p.difficulty AS difficulty,
-                p.se_rating AS se_rating, p.play_count AS play_count,
-                CASE WHEN p.win_count > 0
-                     THEN toFloat(p.total_solve_time) / p.win_count
-                     ELSE 0.0 END AS avg_solve_time,
-                CASE WHEN p.play_count > 0
-                     THEN toFloat(p.win_count) / p.play_count
-                     ELSE 0.0 END AS win_rate,
-                techs",
-    )
-    .param("code", code);
+    let q = query(&format!(
+        "MATCH (p:Puzzle)
+         WHERE p.short_code = $server_code OR p.client_code = $code
+         WITH p ORDER BY CASE WHEN p.short_code = $server_code THEN 0 ELSE 1 END, p.created_at
+         LIMIT 1
+         {PUZZLE_DETAIL_RETURN}"
+    ))
+    .param("code", code)
+    .param("server_code", short_code::normalize(code));
 
     let mut result = graph.execute(q).await?;
     if let Some(row) = result.next().await? {
@@ -187,65 +444,87 @@ pub async fn get_puzzle_by_code(
     }
 }
 
+/// The stored solving path for a puzzle plus the techniques it uses.
+///
+/// Returns `None` if the puzzle does not exist; the path itself is `None`
+/// until the analyzer has processed the puzzle.
+pub async fn get_puzzle_path(
+    graph: &Graph,
+    hash: &str,
+) -> Result<Option<(Option<String>, f32, TechniqueLookup)>, ApiError> {
+    let q = query(
+        "MATCH (p:Puzzle {hash: $hash})
+         OPTIONAL MATCH (p)-[:REQUIRES_TECHNIQUE]->(t:Technique)
+         RETURN p.solve_path AS solve_path,
+                COALESCE(p.max_se_rating, p.se_rating) AS max_se_rating,
+                collect(t.name) AS names,
+                collect(COALESCE(t.display_name, t.name)) AS display_names,
+                collect(COALESCE(t.se_rating, 0.0)) AS se_ratings",
+    )
+    .param("hash", hash);
+
+    let mut result = graph.execute(q).await?;
+    let Some(row) = result.next().await? else {
+        return Ok(None);
+    };
+
+    let names: Vec<String> = row.get("names").unwrap_or_default();
+    let display_names: Vec<String> = row.get("display_names").unwrap_or_default();
+    let se_ratings: Vec<f64> = row.get("se_ratings").unwrap_or_default();
+    let techniques = names
+        .into_iter()
+        .zip(display_names)
+        .zip(se_ratings)
+        .map(|((name, display), se)| (name, (display, se as f32)))
+        .collect();
+
+    Ok(Some((
+        row.get("solve_path").ok(),
+        row.get::<f64>("max_se_rating").unwrap_or(0.0) as f32,
+        techniques,
+    )))
+}
+
 fn row_to_puzzle_detail(row: &neo4rs::Row) -> PuzzleDetail {
     PuzzleDetail {
         puzzle_hash: row.get("puzzle_hash").unwrap_or_default(),
         puzzle_string: row.get("puzzle_string").unwrap_or_default(),
         solution_string: row.get::<String>("solution_string").ok().filter(|s| !s.is_empty()),
+        variant: row_variant(row),
         short_code: row.get("short_code").ok(),
         difficulty: row.get("difficulty").unwrap_or_default(),
         se_rating: row.get::<f64>("se_rating").unwrap_or(0.0) as f32,
+        rating_verified: row.get("rating_verified").unwrap_or(false),
+        state: row.get("state").unwrap_or_default(),
         play_count: row.get::<i64>("play_count").unwrap_or(0) as u64,
         avg_solve_time: row.get("avg_solve_time").unwrap_or(0.0),
         win_rate: row.get("win_rate").unwrap_or(0.0),
         techniques: row.get("techs").unwrap_or_default(),
+        empirical: row.get("empirical").ok(),
+        discoverer: row.get("discoverer").ok(),
+        updated_at: row
+            .get::<i64>("updated_at_ms")
+            .ok()
+            .and_then(DateTime::from_timestamp_millis),
     }
 }
 
 pub async fn get_random_puzzle(
     graph: &Graph,
     difficulty: Option<&str>,
+    variant: Variant,
 ) -> Result<Option<PuzzleDetail>, ApiError> {
-    let cypher = if difficulty.is_some() {
-        "MATCH (p:Puzzle {needs_analysis: false, difficulty: $diff})
-         WHERE p.discovered IS NULL OR p.discovered = true
-         WITH p ORDER BY rand() LIMIT 1
-         OPTIONAL MATCH (p)-[:REQUIRES_TECHNIQUE]->(t:Technique)
-         WITH p, t ORDER BY t.se_rating ASC
-         WITH p, collect(t.name) AS techs
-         RETURN p.hash AS puzzle_hash, p.puzzle_string AS puzzle_string,
-                p.short_code AS short_code, p.difficulty AS difficulty,
-                p.se_rating AS se_rating, p.play_count AS play_count,
-                CASE WHEN p.win_count > 0
-                     THEN toFloat(p.total_solve_time) / p.win_count
-                     ELSE 0.0 END AS avg_solve_time,
-                CASE WHEN p.play_count > 0
-                     THEN toFloat(p.win_count) / p.play_count
-                     ELSE 0.0 END AS win_rate,
-                techs"
-    } else {
-        "MATCH (p:Puzzle {needs_analysis: false})
-         WHERE p.discovered IS NULL OR p.discovered = true
+    // Only classic puzzles get analyzed; other variants are playable once
+    // discovered
+    let q = query(&format!(
+        "MATCH (p:Puzzle {{variant: $variant}})
+         WHERE (p.state = 'analyzed' OR ($variant <> 'classic' AND p.state = 'discovered'))
+           AND ($diff IS NULL OR p.difficulty = $diff)
          WITH p ORDER BY rand() LIMIT 1
-         OPTIONAL MATCH (p)-[:REQUIRES_TECHNIQUE]->(t:Technique)
-         WITH p, t ORDER BY t.se_rating ASC
-         WITH p, collect(t.name) AS techs
-         RETURN p.hash AS puzzle_hash, p.puzzle_string AS puzzle_string,
-                p.short_code AS short_code, p.difficulty AS difficulty,
-                p.se_rating AS se_rating, p.play_count AS play_count,
-                CASE WHEN p.win_count > 0
-                     THEN toFloat(p.total_solve_time) / p.win_count
-                     ELSE 0.0 END AS avg_solve_time,
-                CASE WHEN p.play_count > 0
-                     THEN toFloat(p.win_count) / p.play_count
-                     ELSE 0.0 END AS win_rate,
-                techs"
-    };
-
-    let mut q = query(cypher);
-    if let Some(diff) = difficulty {
-        q = q.param("diff", diff);
-    }
+         {PUZZLE_DETAIL_RETURN}"
+    ))
+    .param("variant", variant.as_str())
+    .param("diff", difficulty);
 
     let mut result = graph.execute(q).await?;
     if let Some(row) = result.next().await? {
@@ -257,13 +536,61 @@ pub async fn get_random_puzzle(
 
 // ── Galaxy queries ───────────────────────────────────────────────────
 
+/// `params.states` restricts nodes to those lifecycle states (see
+/// [`crate::models::lifecycle::public_state_filter`]). Each variant has its
+/// own galaxy.
 pub async fn get_galaxy_overview(
     graph: &Graph,
-    limit: u64,
+    params: &OverviewParams,
 ) -> Result<GalaxyOverview, ApiError> {
-    let node_q = query(
-        "MATCH (p:Puzzle)
-         WITH p ORDER BY p.play_count DESC LIMIT $limit
+    let mut cypher = String::from(
+        "MATCH (p:Puzzle {variant: $variant})
+         WHERE p.state IN $states",
+    );
+    if params.difficulty.is_some() {
+        cypher.push_str(" AND p.difficulty = $difficulty");
+    }
+    if params.as_of.is_some() {
+        cypher.push_str(" AND coalesce(p.discovered_at, p.created_at) <= datetime($as_of)");
+    }
+    if params.technique.is_some() {
+        cypher.push_str(
+            " AND EXISTS {
+                 MATCH (p)-[:REQUIRES_TECHNIQUE]->(ft:Technique)
+                 WHERE ft.name = $technique OR ft.display_name = $technique
+             }",
+        );
+    }
+    cypher.push_str(match params.sort {
+        OverviewSort::Plays => {
+            "
+             WITH p ORDER BY p.play_count DESC, p.hash LIMIT $limit"
+        }
+        OverviewSort::Recent => {
+            "
+             WITH p ORDER BY coalesce(p.discovered_at, p.created_at) DESC, p.hash LIMIT $limit"
+        }
+        OverviewSort::Se => {
+            "
+             WITH p ORDER BY p.se_rating DESC, p.hash LIMIT $limit"
+        }
+        // Take rank 0 from every stratum, then rank 1, ...: strata that run
+        // out leave their share to the others
+        OverviewSort::Sample => {
+            "
+             OPTIONAL MATCH (p)-[:MAX_TECHNIQUE]->(smt:Technique)
+             WITH coalesce(smt.family, 'unknown') AS family, p.difficulty AS tier, p
+             ORDER BY p.hash
+             WITH family, tier, collect(p) AS members
+             UNWIND range(0, size(members) - 1) AS rank
+             WITH members[rank] AS p, rank, family, tier
+             ORDER BY rank, family, tier
+             LIMIT $limit
+             WITH p"
+        }
+    });
+    cypher.push_str(
+        "
          OPTIONAL MATCH (p)-[:REQUIRES_TECHNIQUE]->(t:Technique)
          WITH p, t ORDER BY t.se_rating ASC
          WITH p, collect(t.name) AS techniques
@@ -271,10 +598,24 @@ pub async fn get_galaxy_overview(
          RETURN p.hash AS puzzle_hash, p.puzzle_string AS puzzle_string,
                 p.short_code AS short_code,
                 p.difficulty AS difficulty, p.se_rating AS se_rating,
+                p.max_se_rating IS NOT NULL AS rating_verified,
                 p.play_count AS play_count, mt.name AS max_technique,
                 techniques, p.x AS x, p.y AS y",
-    )
-    .param("limit", limit as i64);
+    );
+
+    let mut node_q = query(&cypher)
+        .param("limit", params.limit as i64)
+        .param("states", params.states.clone())
+        .param("variant", params.variant.as_str());
+    if let Some(difficulty) = &params.difficulty {
+        node_q = node_q.param("difficulty", difficulty.as_str());
+    }
+    if let Some(technique) = &params.technique {
+        node_q = node_q.param("technique", technique.as_str());
+    }
+    if let Some(as_of) = params.as_of {
+        node_q = node_q.param("as_of", as_of.to_rfc3339());
+    }
 
     let mut nodes = Vec::new();
     let mut result = graph.execute(node_q).await?;
@@ -284,7 +625,7 @@ pub async fn get_galaxy_overview(
 
     let hashes: Vec<String> = nodes.iter().map(|n| n.puzzle_hash.clone()).collect();
     let edges = if hashes.len() > 1 {
-        get_edges_for_hashes(graph, &hashes).await?
+        get_edges_for_hashes(graph, &hashes, params.as_of).await?
     } else {
         Vec::new()
     };
@@ -292,16 +633,115 @@ pub async fn get_galaxy_overview(
     Ok(GalaxyOverview { nodes, edges })
 }
 
-async fn get_edges_for_hashes(
+/// Puzzles whose stored layout position falls inside the viewport box,
+/// most played first. The flag is true when more than `limit` matched.
+pub async fn get_viewport_nodes(
+    graph: &Graph,
+    view: &ViewportQuery,
+    limit: u64,
+    states: &[String],
+    variant: Variant,
+) -> Result<(Vec<GalaxyNode>, bool), ApiError> {
+    let q = query(
+        "MATCH (p:Puzzle)
+         WHERE point.withinBBox(p.location, point({x: $minx, y: $miny}), point({x: $maxx, y: $maxy}))
+           AND p.variant = $variant AND p.state IN $states
+         WITH p ORDER BY p.play_count DESC LIMIT $limit
+         OPTIONAL MATCH (p)-[:REQUIRES_TECHNIQUE]->(t:Technique)
+         WITH p, t ORDER BY t.se_rating ASC
+         WITH p, collect(t.name) AS techniques
+         OPTIONAL MATCH (p)-[:MAX_TECHNIQUE]->(mt:Technique)
+         RETURN p.hash AS puzzle_hash, p.puzzle_string AS puzzle_string,
+                p.short_code AS short_code,
+                p.difficulty AS difficulty, p.se_rating AS se_rating,
+                p.max_se_rating IS NOT NULL AS rating_verified,
+                p.play_count AS play_count, mt.name AS max_technique,
+                techniques, p.x AS x, p.y AS y",
+    )
+    .param("minx", view.minx)
+    .param("miny", view.miny)
+    .param("maxx", view.maxx)
+    .param("maxy", view.maxy)
+    // One extra row tells us whether the box was truncated
+    .param("limit", limit as i64 + 1)
+    .param("states", states.to_vec())
+    .param("variant", variant.as_str());
+
+    let mut nodes = Vec::new();
+    let mut result = graph.execute(q).await?;
+    while let Some(row) = result.next().await? {
+        nodes.push(row_to_galaxy_node(&row));
+    }
+    let truncated = nodes.len() as u64 > limit;
+    nodes.truncate(limit as usize);
+    Ok((nodes, truncated))
+}
+
+/// Count puzzles inside the viewport box per `cell`-sized grid square and
+/// difficulty. See [`crate::services::viewport::merge_cells`].
+pub async fn get_viewport_cells(
+    graph: &Graph,
+    view: &ViewportQuery,
+    cell: f64,
+    states: &[String],
+    variant: Variant,
+) -> Result<Vec<CellRow>, ApiError> {
+    let q = query(
+        "MATCH (p:Puzzle)
+         WHERE point.withinBBox(p.location, point({x: $minx, y: $miny}), point({x: $maxx, y: $maxy}))
+           AND p.variant = $variant AND p.state IN $states
+         WITH toInteger(floor(p.x / $cell)) AS cx, toInteger(floor(p.y / $cell)) AS cy,
+              p.difficulty AS difficulty, p
+         RETURN cx, cy, difficulty, count(p) AS count,
+                sum(coalesce(p.play_count, 0)) AS play_count,
+                sum(p.x) AS sum_x, sum(p.y) AS sum_y",
+    )
+    .param("minx", view.minx)
+    .param("miny", view.miny)
+    .param("maxx", view.maxx)
+    .param("maxy", view.maxy)
+    .param("cell", cell)
+    .param("states", states.to_vec())
+    .param("variant", variant.as_str());
+
+    let mut rows = Vec::new();
+    let mut result = graph.execute(q).await?;
+    while let Some(row) = result.next().await? {
+        rows.push(CellRow {
+            cx: row.get("cx").unwrap_or(0),
+            cy: row.get("cy").unwrap_or(0),
+            difficulty: row.get("difficulty").unwrap_or_default(),
+            count: row.get::<i64>("count").unwrap_or(0).max(0) as u64,
+            play_count: row.get::<i64>("play_count").unwrap_or(0).max(0) as u64,
+            sum_x: row.get("sum_x").unwrap_or(0.0),
+            sum_y: row.get("sum_y").unwrap_or(0.0),
+        });
+    }
+    Ok(rows)
+}
+
+/// Similarity edges between `hashes`; with `as_of`, only those the analyzer
+/// had created by then.
+pub async fn get_edges_for_hashes(
     graph: &Graph,
     hashes: &[String],
+    as_of: Option<DateTime<Utc>>,
 ) -> Result<Vec<GalaxyEdge>, ApiError> {
-    let q = query(
+    let mut cypher = String::from(
         "MATCH (a:Puzzle)-[s:SHARES_TECHNIQUE_PROFILE]-(b:Puzzle)
-         WHERE a.hash IN $hashes AND b.hash IN $hashes AND a.hash < b.hash
+         WHERE a.hash IN $hashes AND b.hash IN $hashes AND a.hash < b.hash",
+    );
+    if as_of.is_some() {
+        cypher.push_str(" AND s.created_at <= datetime($as_of)");
+    }
+    cypher.push_str(
+        "
          RETURN a.hash AS source, b.hash AS target, s.similarity AS similarity",
-    )
-    .param("hashes", hashes.to_vec());
+    );
+    let mut q = query(&cypher).param("hashes", hashes.to_vec());
+    if let Some(as_of) = as_of {
+        q = q.param("as_of", as_of.to_rfc3339());
+    }
 
     let mut edges = Vec::new();
     let mut result = graph.execute(q).await?;
@@ -315,12 +755,17 @@ async fn get_edges_for_hashes(
     Ok(edges)
 }
 
+/// Despite the route's name, `family` is a difficulty tier. Communities
+/// detected from the similarity graph are served by [`get_communities`].
 pub async fn get_galaxy_cluster(
     graph: &Graph,
     family: &str,
+    states: &[String],
+    variant: Variant,
 ) -> Result<Vec<GalaxyNode>, ApiError> {
     let q = query(
-        "MATCH (p:Puzzle {difficulty: $family})
+        "MATCH (p:Puzzle {difficulty: $family, variant: $variant})
+         WHERE p.state IN $states
          WITH p ORDER BY p.play_count DESC LIMIT 200
          OPTIONAL MATCH (p)-[:REQUIRES_TECHNIQUE]->(t:Technique)
          WITH p, t ORDER BY t.se_rating ASC
@@ -329,10 +774,13 @@ pub async fn get_galaxy_cluster(
          RETURN p.hash AS puzzle_hash, p.puzzle_string AS puzzle_string,
                 p.short_code AS short_code,
                 p.difficulty AS difficulty, p.se_rating AS se_rating,
+                p.max_se_rating IS NOT NULL AS rating_verified,
                 p.play_count AS play_count, mt.name AS max_technique,
                 techniques, p.x AS x, p.y AS y",
     )
-    .param("family", family);
+    .param("family", family)
+    .param("states", states.to_vec())
+    .param("variant", variant.as_str());
 
     let mut nodes = Vec::new();
     let mut result = graph.execute(q).await?;
@@ -342,12 +790,101 @@ pub async fn get_galaxy_cluster(
     Ok(nodes)
 }
 
+/// Communities written by `ukodus-analyzer communities`, largest first.
+pub async fn get_communities(
+    graph: &Graph,
+    variant: Variant,
+    limit: u64,
+) -> Result<Vec<Community>, ApiError> {
+    let q = query(
+        "MATCH (c:Cluster {variant: $variant})
+         RETURN c.id AS id, c.variant AS variant, c.size AS size,
+                c.min_se_rating AS min_se_rating, c.max_se_rating AS max_se_rating,
+                c.dominant_techniques AS dominant_techniques, c.x AS x, c.y AS y,
+                toString(c.updated_at) AS updated_at
+         ORDER BY c.size DESC, c.id
+         LIMIT $limit",
+    )
+    .param("variant", variant.as_str())
+    .param("limit", limit as i64);
+
+    let mut communities = Vec::new();
+    let mut result = graph.execute(q).await?;
+    while let Some(row) = result.next().await? {
+        communities.push(row_to_community(&row));
+    }
+    Ok(communities)
+}
+
+/// A community with its public members, most played first.
+pub async fn get_community(
+    graph: &Graph,
+    id: &str,
+    limit: u64,
+) -> Result<Option<CommunityDetail>, ApiError> {
+    let q = query(
+        "MATCH (c:Cluster {id: $id})
+         RETURN c.id AS id, c.variant AS variant, c.size AS size,
+                c.min_se_rating AS min_se_rating, c.max_se_rating AS max_se_rating,
+                c.dominant_techniques AS dominant_techniques, c.x AS x, c.y AS y,
+                toString(c.updated_at) AS updated_at",
+    )
+    .param("id", id);
+
+    let mut result = graph.execute(q).await?;
+    let Some(row) = result.next().await? else {
+        return Ok(None);
+    };
+    let community = row_to_community(&row);
+
+    let q = query(
+        "MATCH (p:Puzzle)-[:IN_CLUSTER]->(:Cluster {id: $id})
+         WHERE p.state IN $public
+         WITH p ORDER BY p.play_count DESC LIMIT $limit
+         OPTIONAL MATCH (p)-[:REQUIRES_TECHNIQUE]->(t:Technique)
+         WITH p, t ORDER BY t.se_rating ASC
+         WITH p, collect(t.name) AS techniques
+         OPTIONAL MATCH (p)-[:MAX_TECHNIQUE]->(mt:Technique)
+         RETURN p.hash AS puzzle_hash, p.puzzle_string AS puzzle_string,
+                p.short_code AS short_code,
+                p.difficulty AS difficulty, p.se_rating AS se_rating,
+                p.max_se_rating IS NOT NULL AS rating_verified,
+                p.play_count AS play_count, mt.name AS max_technique,
+                techniques, p.x AS x, p.y AS y",
+    )
+    .param("id", id)
+    .param("public", PuzzleState::names(PuzzleState::PUBLIC))
+    .param("limit", limit as i64);
+
+    let mut nodes = Vec::new();
+    let mut result = graph.execute(q).await?;
+    while let Some(row) = result.next().await? {
+        nodes.push(row_to_galaxy_node(&row));
+    }
+    Ok(Some(CommunityDetail { community, nodes }))
+}
+
+fn row_to_community(row: &neo4rs::Row) -> Community {
+    Community {
+        id: row.get("id").unwrap_or_default(),
+        variant: row_variant(row),
+        size: row.get::<i64>("size").unwrap_or(0).max(0) as u64,
+        min_se_rating: row.get("min_se_rating").unwrap_or(0.0),
+        max_se_rating: row.get("max_se_rating").unwrap_or(0.0),
+        dominant_techniques: row.get("dominant_techniques").unwrap_or_default(),
+        x: row.get("x").ok(),
+        y: row.get("y").ok(),
+        updated_at: row.get("updated_at").ok(),
+    }
+}
+
 pub async fn get_galaxy_neighbors(
     graph: &Graph,
     hash: &str,
 ) -> Result<GalaxyOverview, ApiError> {
     let q = query(
         "MATCH (p:Puzzle {hash: $hash})-[s:SHARES_TECHNIQUE_PROFILE]-(n:Puzzle)
+         WHERE n.state IN $public AND n.variant = p.variant
          WITH n, s, p ORDER BY s.similarity DESC LIMIT 50
          OPTIONAL MATCH (n)-[:REQUIRES_TECHNIQUE]->(t:Technique)
          WITH n, s, p, t ORDER BY t.se_rating ASC
@@ -356,11 +893,13 @@ pub async fn get_galaxy_neighbors(
          RETURN n.hash AS puzzle_hash, n.puzzle_string AS puzzle_string,
                 n.short_code AS short_code,
                 n.difficulty AS difficulty, n.se_rating AS se_rating,
+                n.max_se_rating IS NOT NULL AS rating_verified,
                 n.play_count AS play_count, mt.name AS max_technique,
                 techniques, n.x AS x, n.y AS y,
                 s.similarity AS similarity, p.hash AS origin",
     )
-    .param("hash", hash);
+    .param("hash", hash)
+    .param("public", PuzzleState::names(PuzzleState::PUBLIC));
 
     let mut nodes = Vec::new();
     let mut edges = Vec::new();
@@ -377,9 +916,79 @@ pub async fn get_galaxy_neighbors(
     Ok(GalaxyOverview { nodes, edges })
 }
 
-pub async fn get_galaxy_stats(graph: &Graph) -> Result<GalaxyStats, ApiError> {
+/// A public puzzle as a learning-path step, with its variant.
+/// `new_techniques` is left empty for the caller to fill.
+pub async fn get_path_puzzle(
+    graph: &Graph,
+    hash: &str,
+) -> Result<Option<(LearningStep, Variant)>, ApiError> {
     let q = query(
-        "MATCH (p:Puzzle)
+        "MATCH (p:Puzzle {hash: $hash})
+         WHERE p.state IN $public
+         OPTIONAL MATCH (p)-[:REQUIRES_TECHNIQUE]->(t:Technique)
+         WITH p, t ORDER BY t.se_rating ASC
+         RETURN p.hash AS puzzle_hash, p.short_code AS short_code,
+                p.difficulty AS difficulty, p.se_rating AS se_rating,
+                p.variant AS variant, collect(t.name) AS techniques",
+    )
+    .param("hash", hash)
+    .param("public", PuzzleState::names(PuzzleState::PUBLIC));
+
+    let mut result = graph.execute(q).await?;
+    Ok(result
+        .next()
+        .await?
+        .map(|row| (row_to_learning_step(&row), row_variant(&row))))
+}
+
+/// Public puzzles rated between `min_se` and `max_se` (inclusive), the
+/// stepping stones a learning path may use. Most played first.
+pub async fn get_path_candidates(
+    graph: &Graph,
+    variant: Variant,
+    min_se: f64,
+    max_se: f64,
+    limit: u64,
+) -> Result<Vec<LearningStep>, ApiError> {
+    let q = query(
+        "MATCH (p:Puzzle {variant: $variant})
+         WHERE p.state IN $public AND p.se_rating >= $min_se AND p.se_rating <= $max_se
+         WITH p ORDER BY p.play_count DESC LIMIT $limit
+         OPTIONAL MATCH (p)-[:REQUIRES_TECHNIQUE]->(t:Technique)
+         WITH p, t ORDER BY t.se_rating ASC
+         RETURN p.hash AS puzzle_hash, p.short_code AS short_code,
+                p.difficulty AS difficulty, p.se_rating AS se_rating,
+                collect(t.name) AS techniques",
+    )
+    .param("variant", variant.as_str())
+    .param("public", PuzzleState::names(PuzzleState::PUBLIC))
+    .param("min_se", min_se)
+    .param("max_se", max_se)
+    .param("limit", limit as i64);
+
+    let mut steps = Vec::new();
+    let mut result = graph.execute(q).await?;
+    while let Some(row) = result.next().await? {
+        steps.push(row_to_learning_step(&row));
+    }
+    Ok(steps)
+}
+
+fn row_to_learning_step(row: &neo4rs::Row) -> LearningStep {
+    LearningStep {
+        puzzle_hash: row.get("puzzle_hash").unwrap_or_default(),
+        short_code: row.get("short_code").ok(),
+        difficulty: row.get("difficulty").unwrap_or_default(),
+        se_rating: row.get("se_rating").unwrap_or(0.0),
+        techniques: row.get("techniques").unwrap_or_default(),
+        new_techniques: Vec::new(),
+    }
+}
+
+pub async fn get_galaxy_stats(graph: &Graph, variant: Variant) -> Result<GalaxyStats, ApiError> {
+    let q = query(
+        "MATCH (p:Puzzle {variant: $variant})
+         WHERE p.state IN $public
          WITH count(p) AS total_puzzles,
               sum(p.play_count) AS total_plays,
               CASE WHEN sum(p.win_count) > 0
@@ -387,7 +996,9 @@ pub async fn get_galaxy_stats(graph: &Graph) -> Result<GalaxyStats, ApiError> {
                    ELSE 0.0 END AS avg_solve_time
          OPTIONAL MATCH (t:Technique)
          RETURN total_puzzles, total_plays, count(t) AS total_techniques, avg_solve_time",
-    );
+    )
+    .param("public", PuzzleState::names(PuzzleState::PUBLIC))
+    .param("variant", variant.as_str());
 
     let mut result = graph.execute(q).await?;
     if let Some(row) = result.next().await? {
@@ -407,12 +1018,65 @@ pub async fn get_galaxy_stats(graph: &Graph) -> Result<GalaxyStats, ApiError> {
     }
 }
 
+/// Puzzles discovered and similarity edges created per `bucket`, as (bucket
+/// start in epoch milliseconds, count) pairs. Puzzles count from discovery
+/// (mined ones are hidden until then); edges only when both ends are public.
+pub async fn get_timeline_counts(
+    graph: &Graph,
+    bucket: TimelineBucket,
+    states: &[String],
+    variant: Variant,
+) -> Result<(Vec<(i64, u64)>, Vec<(i64, u64)>), ApiError> {
+    let node_q = query(
+        "MATCH (p:Puzzle {variant: $variant})
+         WHERE p.state IN $states
+         WITH datetime.truncate($unit, coalesce(p.discovered_at, p.created_at)) AS start
+         WHERE start IS NOT NULL
+         RETURN start.epochMillis AS start_ms, count(*) AS count",
+    )
+    .param("unit", bucket.as_str())
+    .param("states", states.to_vec())
+    .param("variant", variant.as_str());
+
+    let mut nodes = Vec::new();
+    let mut result = graph.execute(node_q).await?;
+    while let Some(row) = result.next().await? {
+        nodes.push((
+            row.get::<i64>("start_ms").unwrap_or(0),
+            row.get::<i64>("count").unwrap_or(0) as u64,
+        ));
+    }
+
+    // Directed pattern: each undirected edge is counted once
+    let edge_q = query(
+        "MATCH (a:Puzzle {variant: $variant})-[s:SHARES_TECHNIQUE_PROFILE]->(b:Puzzle)
+         WHERE a.state IN $states AND b.state IN $states AND s.created_at IS NOT NULL
+         WITH datetime.truncate($unit, s.created_at) AS start
+         RETURN start.epochMillis AS start_ms, count(*) AS count",
+    )
+    .param("unit", bucket.as_str())
+    .param("states", states.to_vec())
+    .param("variant", variant.as_str());
+
+    let mut edges = Vec::new();
+    let mut result = graph.execute(edge_q).await?;
+    while let Some(row) = result.next().await? {
+        edges.push((
+            row.get::<i64>("start_ms").unwrap_or(0),
+            row.get::<i64>("count").unwrap_or(0) as u64,
+        ));
+    }
+    Ok((nodes, edges))
+}
+
 pub async fn get_recent_plays(
     graph: &Graph,
     limit: u64,
+    variant: Variant,
 ) -> Result<Vec<GalaxyNode>, ApiError> {
     let q = query(
-        "MATCH (r:GameResult)-[:FOR_PUZZLE]->(p:Puzzle)
+        "MATCH (r:GameResult)-[:FOR_PUZZLE]->(p:Puzzle {variant: $variant})
+         WHERE p.state IN $public
          WITH p, max(r.created_at) AS latest
          ORDER BY latest DESC LIMIT $limit
          OPTIONAL MATCH (p)-[:REQUIRES_TECHNIQUE]->(t:Technique)
@@ -422,10 +1086,13 @@ pub async fn get_recent_plays(
          RETURN p.hash AS puzzle_hash, p.puzzle_string AS puzzle_string,
                 p.short_code AS short_code,
                 p.difficulty AS difficulty, p.se_rating AS se_rating,
+                p.max_se_rating IS NOT NULL AS rating_verified,
                 p.play_count AS play_count, mt.name AS max_technique,
                 techniques, p.x AS x, p.y AS y",
     )
-    .param("limit", limit as i64);
+    .param("limit", limit as i64)
+    .param("public", PuzzleState::names(PuzzleState::PUBLIC))
+    .param("variant", variant.as_str());
 
     let mut nodes = Vec::new();
     let mut result = graph.execute(q).await?;
@@ -442,6 +1109,7 @@ fn row_to_galaxy_node(row: &neo4rs::Row) -> GalaxyNode {
         short_code: row.get("short_code").ok(),
         difficulty: row.get("difficulty").unwrap_or_default(),
         se_rating: row.get::<f64>("se_rating").unwrap_or(0.0) as f32,
+        rating_verified: row.get("rating_verified").unwrap_or(false),
         play_count: row.get::<i64>("play_count").unwrap_or(0) as u64,
         max_technique: row.get("max_technique").ok(),
         techniques: row.get("techniques").unwrap_or_default(),
@@ -471,6 +1139,146 @@ pub async fn get_all_techniques(graph: &Graph) -> Result<Vec<TechniqueInfo>, Api
     Ok(techniques)
 }
 
+/// The enum name of the technique called `name` (enum or display name),
+/// or `None` if there is no such technique.
+pub async fn resolve_technique_name(graph: &Graph, name: &str) -> Result<Option<String>, ApiError> {
+    let q = query(
+        "MATCH (t:Technique)
+         WHERE t.name = $name OR t.display_name = $name
+         RETURN t.name AS name
+         LIMIT 1",
+    )
+    .param("name", name);
+
+    let mut result = graph.execute(q).await?;
+    if let Some(row) = result.next().await? {
+        Ok(row.get::<String>("name").ok())
+    } else {
+        Ok(None)
+    }
+}
+
+/// Every technique with its `CO_OCCURS_WITH` edges (written by
+/// `ukodus-analyzer co-occurrence`) at or above the given thresholds.
+pub async fn get_technique_graph(
+    graph: &Graph,
+    min_count: u64,
+    min_lift: f64,
+) -> Result<TechniqueGraph, ApiError> {
+    let nodes = get_technique_nodes(graph).await?;
+
+    let q = query(
+        "MATCH (a:Technique)-[r:CO_OCCURS_WITH]->(b:Technique)
+         WHERE r.count >= $min_count AND r.lift >= $min_lift
+         RETURN a.name AS source, b.name AS target, r.count AS count, r.lift AS lift
+         ORDER BY r.lift DESC",
+    )
+    .param("min_count", min_count as i64)
+    .param("min_lift", min_lift);
+
+    let mut edges = Vec::new();
+    let mut result = graph.execute(q).await?;
+    while let Some(row) = result.next().await? {
+        edges.push(TechniqueEdge {
+            source: row.get("source").unwrap_or_default(),
+            target: row.get("target").unwrap_or_default(),
+            count: row.get::<i64>("count").unwrap_or(0).max(0) as u64,
+            lift: row.get("lift").unwrap_or(0.0),
+        });
+    }
+    Ok(TechniqueGraph { nodes, edges })
+}
+
+/// Every technique with its count of public puzzles requiring it, easiest first.
+async fn get_technique_nodes(graph: &Graph) -> Result<Vec<TechniqueNode>, ApiError> {
+    let q = query(
+        "MATCH (t:Technique)
+         OPTIONAL MATCH (p:Puzzle)-[:REQUIRES_TECHNIQUE]->(t)
+         WHERE p.state IN $public
+         RETURN t.name AS name, t.display_name AS display_name, t.family AS family,
+                t.se_rating AS se_rating, count(p) AS puzzle_count
+         ORDER BY t.se_rating, t.name",
+    )
+    .param("public", PuzzleState::names(PuzzleState::PUBLIC));
+
+    let mut nodes = Vec::new();
+    let mut result = graph.execute(q).await?;
+    while let Some(row) = result.next().await? {
+        nodes.push(TechniqueNode {
+            name: row.get("name").unwrap_or_default(),
+            display_name: row.get("display_name").ok(),
+            family: row.get("family").ok(),
+            se_rating: row.get("se_rating").ok(),
+            puzzle_count: row.get::<i64>("puzzle_count").unwrap_or(0).max(0) as u64,
+        });
+    }
+    Ok(nodes)
+}
+
+/// The curated `PREREQUISITE_OF` DAG seeded by `ukodus-analyzer seed-techniques`.
+pub async fn get_prerequisite_graph(graph: &Graph) -> Result<PrerequisiteGraph, ApiError> {
+    let nodes = get_technique_nodes(graph).await?;
+
+    let q = query(
+        "MATCH (a:Technique)-[:PREREQUISITE_OF]->(b:Technique)
+         RETURN a.name AS prerequisite, b.name AS technique
+         ORDER BY a.se_rating, a.name, b.name",
+    );
+    let mut edges = Vec::new();
+    let mut result = graph.execute(q).await?;
+    while let Some(row) = result.next().await? {
+        edges.push(PrerequisiteEdge {
+            prerequisite: row.get("prerequisite").unwrap_or_default(),
+            technique: row.get("technique").unwrap_or_default(),
+        });
+    }
+    Ok(PrerequisiteGraph { nodes, edges })
+}
+
+/// Every technique reachable backwards over `PREREQUISITE_OF` from `name`
+/// (enum or display name). `None` when the technique does not exist.
+///
+/// Sorting by the longest path to the technique, longest first, is a
+/// topological order: a prerequisite is always further back than anything
+/// it leads to.
+pub async fn get_technique_prerequisites(
+    graph: &Graph,
+    name: &str,
+) -> Result<Option<TechniquePrerequisites>, ApiError> {
+    let q = query(
+        "MATCH (t:Technique)
+         WHERE t.name = $name OR t.display_name = $name
+         WITH t LIMIT 1
+         OPTIONAL MATCH path = (p:Technique)-[:PREREQUISITE_OF*1..]->(t)
+         WITH t, p, min(length(path)) AS distance, max(length(path)) AS depth
+         RETURN t.name AS technique, p.name AS name, p.display_name AS display_name,
+                p.se_rating AS se_rating, distance
+         ORDER BY depth DESC, p.se_rating, p.name",
+    )
+    .param("name", name);
+
+    // One row per prerequisite; a technique without any yields a single
+    // row with a null `name`
+    let mut technique = None;
+    let mut prerequisites = Vec::new();
+    let mut result = graph.execute(q).await?;
+    while let Some(row) = result.next().await? {
+        technique.get_or_insert_with(|| row.get::<String>("technique").unwrap_or_default());
+        if let Ok(name) = row.get::<String>("name") {
+            prerequisites.push(Prerequisite {
+                name,
+                display_name: row.get("display_name").ok(),
+                se_rating: row.get("se_rating").ok(),
+                distance: row.get::<i64>("distance").unwrap_or(0).max(0) as u64,
+            });
+        }
+    }
+    Ok(technique.map(|technique| TechniquePrerequisites {
+        technique,
+        prerequisites,
+    }))
+}
+
 pub async fn get_puzzles_by_technique(
     graph: &Graph,
     name: &str,
@@ -478,6 +1286,7 @@ pub async fn get_puzzles_by_technique(
 ) -> Result<Vec<GalaxyNode>, ApiError> {
     let q = query(
         "MATCH (p:Puzzle)-[:REQUIRES_TECHNIQUE]->(t:Technique {name: $name})
+         WHERE p.state IN $public
          WITH p ORDER BY p.play_count DESC LIMIT $limit
          OPTIONAL MATCH (p)-[:REQUIRES_TECHNIQUE]->(t2:Technique)
          WITH p, t2 ORDER BY t2.se_rating ASC
@@ -486,11 +1295,13 @@ pub async fn get_puzzles_by_technique(
          RETURN p.hash AS puzzle_hash, p.puzzle_string AS puzzle_string,
                 p.short_code AS short_code,
                 p.difficulty AS difficulty, p.se_rating AS se_rating,
+                p.max_se_rating IS NOT NULL AS rating_verified,
                 p.play_count AS play_count, mt.name AS max_technique,
                 techniques, p.x AS x, p.y AS y",
     )
     .param("name", name)
-    .param("limit", limit as i64);
+    .param("limit", limit as i64)
+    .param("public", PuzzleState::names(PuzzleState::PUBLIC));
 
     let mut nodes = Vec::new();
     let mut result = graph.execute(q).await?;
@@ -508,50 +1319,42 @@ pub async fn upsert_shared_puzzle(
     base_url: &str,
 ) -> Result<ShareResponse, ApiError> {
     let share_id = Uuid::new_v4().to_string();
-    let puzzle_hash = format!("{:x}", md5_hash(&input.puzzle_string));
+    let puzzle_hash = resolve_puzzle_hash(graph, &input.puzzle_string, input.variant).await?;
 
     let q = query(
         "MERGE (s:Share {puzzle_hash: $phash, player_id: $player})
-         ON CREATE SET s.share_id = $sid, s.puzzle_string = $ps,
-                       s.short_code = $sc, s.difficulty = $diff,
+         ON CREATE SET s.share_id = $sid, s.puzzle_string = $ps, s.variant = $variant,
+                       s.client_code = $cc, s.difficulty = $diff,
                        s.se_rating = $rating, s.platform = $platform,
                        s.created_at = datetime()
          ON MATCH SET  s.platform = $platform
-         RETURN s.share_id AS share_id, s.short_code AS short_code",
+         RETURN s.share_id AS share_id",
     )
     .param("phash", puzzle_hash.as_str())
     .param("player", input.player_id.as_str())
     .param("sid", share_id.as_str())
     .param("ps", input.puzzle_string.as_str())
-    .param("sc", input.short_code.as_deref().unwrap_or(""))
+    .param("variant", input.variant.as_str())
+    .param("cc", input.short_code.as_deref().filter(|c| !c.is_empty()))
     .param("diff", input.difficulty.as_str())
     .param("rating", input.se_rating as f64)
     .param("platform", input.platform.as_str());
 
     let mut result = graph.execute(q).await?;
-    let (final_id, final_code) = if let Some(row) = result.next().await? {
-        let id: String = row.get("share_id").unwrap_or(share_id);
-        let code: Option<String> = row.get("short_code").ok();
-        (id, code)
+    let final_id = if let Some(row) = result.next().await? {
+        row.get::<String>("share_id").unwrap_or(share_id)
     } else {
-        (share_id, input.short_code.clone())
+        share_id
     };
 
+    let code = ensure_share_short_code(graph, &final_id).await?;
     let share_url = format!("{}/s/{}", base_url, final_id);
-    let qr_data = if let Some(ref code) = final_code {
-        if !code.is_empty() {
-            format!("{}/play/?s={}", base_url, code)
-        } else {
-            share_url.clone()
-        }
-    } else {
-        share_url.clone()
-    };
+    let qr_data = format!("{}/s/{}", base_url, code);
 
     Ok(ShareResponse {
         share_id: final_id,
         share_url,
-        short_code: final_code.filter(|c| !c.is_empty()),
+        short_code: Some(code),
         qr_data,
     })
 }
@@ -563,7 +1366,9 @@ pub async fn get_share_by_id(
     let q = query(
         "MATCH (s:Share {share_id: $id})
          RETURN s.share_id AS share_id, s.puzzle_hash AS puzzle_hash,
-                s.puzzle_string AS puzzle_string, s.short_code AS short_code,
+                s.puzzle_string AS puzzle_string, s.variant AS variant,
+                s.short_code AS short_code,
+                s.client_code AS client_code,
                 s.difficulty AS difficulty, s.se_rating AS se_rating,
                 s.platform AS platform, s.player_id AS player_id,
                 toString(s.created_at) AS created_at",
@@ -583,15 +1388,20 @@ pub async fn get_share_by_code(
     code: &str,
 ) -> Result<Option<ShareDetail>, ApiError> {
     let q = query(
-        "MATCH (s:Share {short_code: $code})
+        "MATCH (s:Share)
+         WHERE s.short_code = $server_code OR s.client_code = $code
          RETURN s.share_id AS share_id, s.puzzle_hash AS puzzle_hash,
-                s.puzzle_string AS puzzle_string, s.short_code AS short_code,
+                s.puzzle_string AS puzzle_string, s.variant AS variant,
+                s.short_code AS short_code,
+                s.client_code AS client_code,
                 s.difficulty AS difficulty, s.se_rating AS se_rating,
                 s.platform AS platform, s.player_id AS player_id,
                 toString(s.created_at) AS created_at
+         ORDER BY CASE WHEN s.short_code = $server_code THEN 0 ELSE 1 END, s.created_at
          LIMIT 1",
     )
-    .param("code", code);
+    .param("code", code)
+    .param("server_code", short_code::normalize(code));
 
     let mut result = graph.execute(q).await?;
     if let Some(row) = result.next().await? {
@@ -608,7 +1418,9 @@ pub async fn get_recent_shares(
     let q = query(
         "MATCH (s:Share)
          RETURN s.share_id AS share_id, s.puzzle_hash AS puzzle_hash,
-                s.puzzle_string AS puzzle_string, s.short_code AS short_code,
+                s.puzzle_string AS puzzle_string, s.variant AS variant,
+                s.short_code AS short_code,
+                s.client_code AS client_code,
                 s.difficulty AS difficulty, s.se_rating AS se_rating,
                 s.platform AS platform, s.player_id AS player_id,
                 toString(s.created_at) AS created_at
@@ -631,7 +1443,9 @@ fn row_to_share_detail(row: &neo4rs::Row, _base_url: &str) -> ShareDetail {
         share_id: share_id.clone(),
         puzzle_hash: row.get("puzzle_hash").unwrap_or_default(),
         puzzle_string: row.get("puzzle_string").unwrap_or_default(),
+        variant: row_variant(row),
         short_code: row.get("short_code").ok().filter(|s: &String| !s.is_empty()),
+        client_code: row.get("client_code").ok().filter(|s: &String| !s.is_empty()),
         difficulty: row.get("difficulty").unwrap_or_default(),
         se_rating: row.get::<f64>("se_rating").unwrap_or(0.0) as f32,
         platform: row.get("platform").unwrap_or_default(),
@@ -643,8 +1457,10 @@ fn row_to_share_detail(row: &neo4rs::Row, _base_url: &str) -> ShareDetail {
 
 // ── Leaderboard ──────────────────────────────────────────────────────
 
+/// Boards are per variant; a `puzzle_hash` board already implies one.
 pub async fn get_leaderboard(
     graph: &Graph,
+    variant: Variant,
     difficulty: Option<&str>,
     puzzle_hash: Option<&str>,
     limit: u64,
@@ -660,7 +1476,7 @@ pub async fn get_leaderboard(
          ORDER BY r.time_secs ASC
          SKIP $offset LIMIT $limit"
     } else if difficulty.is_some() {
-        "MATCH (r:GameResult)-[:FOR_PUZZLE]->(p:Puzzle {difficulty: $diff})
+        "MATCH (r:GameResult)-[:FOR_PUZZLE]->(p:Puzzle {difficulty: $diff, variant: $variant})
          WHERE r.result = 'Win' AND r.verified = true AND r.hints_used = 0 AND r.mistakes < 3
          RETURN r.player_id AS player_id, r.player_tag AS player_tag,
                 r.time_secs AS time_secs,
@@ -669,7 +1485,7 @@ pub async fn get_leaderboard(
          ORDER BY r.time_secs ASC
          SKIP $offset LIMIT $limit"
     } else {
-        "MATCH (r:GameResult)-[:FOR_PUZZLE]->(p:Puzzle)
+        "MATCH (r:GameResult)-[:FOR_PUZZLE]->(p:Puzzle {variant: $variant})
          WHERE r.result = 'Win' AND r.verified = true AND r.hints_used = 0 AND r.mistakes < 3
          RETURN r.player_id AS player_id, r.player_tag AS player_tag,
                 r.time_secs AS time_secs,
@@ -680,6 +1496,7 @@ pub async fn get_leaderboard(
     };
 
     let mut q = query(cypher)
+        .param("variant", variant.as_str())
         .param("limit", limit as i64)
         .param("offset", offset as i64);
 
@@ -706,46 +1523,48 @@ pub async fn get_leaderboard(
 
 // ── Mining ───────────────────────────────────────────────────────
 
+/// `input` must already hold canonical grid strings.
+/// Store a mined puzzle, returning its hash (see [`resolve_puzzle_hash`])
+/// and whether it was already known.
 pub async fn upsert_mined_puzzle(
     graph: &Graph,
-    hash: &str,
-    puzzle_string: &str,
-    solution_string: &str,
-    difficulty: &str,
-    se_rating: f32,
-    short_code: Option<&str>,
-) -> Result<bool, ApiError> {
+    input: &MinedPuzzleInput,
+) -> Result<(String, bool), ApiError> {
+    let puzzle_hash = resolve_puzzle_hash(graph, &input.puzzle_string, input.variant).await?;
     let q = query(
         "MERGE (p:Puzzle {hash: $hash})
          ON CREATE SET
            p.puzzle_string = $ps,
            p.solution_string = $sol,
+           p.variant = $variant,
            p.difficulty = $diff,
            p.se_rating = $rating,
-           p.short_code = $sc,
-           p.discovered = false,
-           p.mined = true,
-           p.needs_analysis = false,
+           p.client_code = $cc,
+           p.source = 'mined',
+           p.state = 'mined',
+           p.state_changed_at = datetime(),
            p.play_count = 0,
            p.total_solve_time = 0,
            p.win_count = 0,
-           p.created_at = datetime()
+           p.created_at = datetime(),
+           p.updated_at = datetime()
          RETURN p.hash AS hash,
                 CASE WHEN p.created_at < datetime() - duration('PT1S') THEN true ELSE false END AS duplicate",
     )
-    .param("hash", hash)
-    .param("ps", puzzle_string)
-    .param("sol", solution_string)
-    .param("diff", difficulty)
-    .param("rating", se_rating as f64)
-    .param("sc", short_code.unwrap_or(""));
+    .param("hash", puzzle_hash.as_str())
+    .param("ps", input.puzzle_string.as_str())
+    .param("sol", input.solution_string.as_str())
+    .param("variant", input.variant.as_str())
+    .param("diff", input.difficulty.as_str())
+    .param("rating", input.se_rating as f64)
+    .param("cc", input.short_code.as_deref().filter(|c| !c.is_empty()));
 
     let mut result = graph.execute(q).await?;
     if let Some(row) = result.next().await? {
         let duplicate: bool = row.get("duplicate").unwrap_or(false);
-        Ok(duplicate)
+        Ok((puzzle_hash, duplicate))
     } else {
-        Ok(false)
+        Ok((puzzle_hash, false))
     }
 }
 
@@ -754,21 +1573,25 @@ pub async fn get_undiscovered_puzzle(
     difficulty: Option<&str>,
 ) -> Result<Option<PuzzleDetail>, ApiError> {
     let cypher = if difficulty.is_some() {
-        "MATCH (p:Puzzle {discovered: false, mined: true, difficulty: $diff})
+        "MATCH (p:Puzzle {state: 'mined', difficulty: $diff})
          WITH p ORDER BY rand() LIMIT 1
          RETURN p.hash AS puzzle_hash, p.puzzle_string AS puzzle_string,
                 p.solution_string AS solution_string,
                 p.short_code AS short_code, p.difficulty AS difficulty,
-                p.se_rating AS se_rating, p.play_count AS play_count,
+                p.se_rating AS se_rating, p.max_se_rating IS NOT NULL AS rating_verified,
+                p.state AS state, p.variant AS variant,
+                p.play_count AS play_count,
                 0.0 AS avg_solve_time, 0.0 AS win_rate,
                 [] AS techs"
     } else {
-        "MATCH (p:Puzzle {discovered: false, mined: true})
+        "MATCH (p:Puzzle {state: 'mined'})
          WITH p ORDER BY rand() LIMIT 1
          RETURN p.hash AS puzzle_hash, p.puzzle_string AS puzzle_string,
                 p.solution_string AS solution_string,
                 p.short_code AS short_code, p.difficulty AS difficulty,
-                p.se_rating AS se_rating, p.play_count AS play_count,
+                p.se_rating AS se_rating, p.max_se_rating IS NOT NULL AS rating_verified,
+                p.state AS state, p.variant AS variant,
+                p.play_count AS play_count,
                 0.0 AS avg_solve_time, 0.0 AS win_rate,
                 [] AS techs"
     };
@@ -786,21 +1609,88 @@ pub async fn get_undiscovered_puzzle(
     }
 }
 
-pub async fn mark_puzzle_discovered(graph: &Graph, hash: &str) -> Result<(), ApiError> {
+/// Credit the first result on a mined puzzle with its discovery. Callers
+/// only pass verified wins.
+///
+/// Returns the new `puzzle_discovered` event payload, or `None` if the
+/// puzzle was not waiting to be discovered.
+///
+/// The state check, transition and edge are one statement. Writing `_lock`
+/// first takes the puzzle's write lock, so of two concurrent first results
+/// the second waits and then sees the puzzle already discovered.
+pub async fn mark_puzzle_discovered(
+    graph: &Graph,
+    hash: &str,
+    result_id: &str,
+) -> Result<Option<serde_json::Value>, ApiError> {
     let q = query(
         "MATCH (p:Puzzle {hash: $hash})
-         WHERE p.discovered = false
-         SET p.discovered = true",
+         SET p._lock = true
+         REMOVE p._lock
+         WITH p
+         WHERE p.state IN $from AND NOT ()-[:DISCOVERED]->(p)
+         MATCH (r:GameResult {id: $rid})
+         SET p.state = 'discovered', p.state_changed_at = datetime(),
+             p.discovered_at = datetime(), p.updated_at = datetime()
+         CREATE (r)-[d:DISCOVERED {discovered_at: p.discovered_at}]->(p)
+         RETURN p.hash AS puzzle_hash, p.variant AS variant, p.short_code AS short_code,
+                p.difficulty AS difficulty, p.se_rating AS se_rating,
+                r.player_tag AS player_tag, toString(d.discovered_at) AS discovered_at",
     )
-    .param("hash", hash);
+    .param("hash", hash)
+    .param("rid", result_id)
+    .param("from", PuzzleState::names(PuzzleState::Discovered.allowed_from()));
 
-    graph.run(q).await?;
-    Ok(())
+    let mut result = graph.execute(q).await?;
+    let Some(row) = result.next().await? else {
+        return Ok(None);
+    };
+    Ok(Some(serde_json::json!({
+        "puzzle_hash": row.get::<String>("puzzle_hash").unwrap_or_default(),
+        "variant": row_variant(&row).as_str(),
+        "short_code": row.get::<String>("short_code").ok(),
+        "difficulty": row.get::<String>("difficulty").unwrap_or_default(),
+        "se_rating": row.get::<f64>("se_rating").unwrap_or(0.0),
+        "player_tag": row.get::<String>("player_tag").ok().filter(|t| !t.is_empty()),
+        "discovered_at": row.get::<String>("discovered_at").unwrap_or_default(),
+    })))
+}
+
+/// Players ranked by the number of mined puzzles they discovered.
+pub async fn get_top_discoverers(
+    graph: &Graph,
+    limit: u64,
+) -> Result<Vec<DiscovererEntry>, ApiError> {
+    let q = query(
+        "MATCH (r:GameResult)-[d:DISCOVERED]->(p:Puzzle)
+         WHERE p.state <> 'retired' AND r.verified = true
+         WITH r.player_id AS player_id, d, r
+         ORDER BY d.discovered_at ASC
+         WITH player_id, last(collect(r.player_tag)) AS player_tag,
+              count(d) AS discoveries, max(d.discovered_at) AS last_discovered_at
+         RETURN player_id, player_tag, discoveries,
+                toString(last_discovered_at) AS last_discovered_at
+         ORDER BY discoveries DESC, last_discovered_at ASC
+         LIMIT $limit",
+    )
+    .param("limit", limit as i64);
+
+    let mut entries = Vec::new();
+    let mut result = graph.execute(q).await?;
+    while let Some(row) = result.next().await? {
+        entries.push(DiscovererEntry {
+            player_id: row.get("player_id").unwrap_or_default(),
+            player_tag: row.get::<String>("player_tag").ok().filter(|s| !s.is_empty()),
+            discoveries: row.get::<i64>("discoveries").unwrap_or(0) as u64,
+            last_discovered_at: row.get("last_discovered_at").unwrap_or_default(),
+        });
+    }
+    Ok(entries)
 }
 
 pub async fn get_pool_inventory(graph: &Graph) -> Result<Vec<PoolCount>, ApiError> {
     let q = query(
-        "MATCH (p:Puzzle {discovered: false, mined: true})
+        "MATCH (p:Puzzle {state: 'mined'})
          RETURN p.difficulty AS difficulty, count(p) AS count",
     );
     let mut result = graph.execute(q).await?;
@@ -816,9 +1706,9 @@ pub async fn get_pool_inventory(graph: &Graph) -> Result<Vec<PoolCount>, ApiErro
 
 pub async fn get_pool_monitoring(graph: &Graph) -> Result<Vec<PoolStats>, ApiError> {
     let q = query(
-        "MATCH (p:Puzzle {mined: true})
+        "MATCH (p:Puzzle {source: 'mined'})
          WITH p.difficulty AS difficulty,
-              CASE WHEN p.discovered = false THEN 1 ELSE 0 END AS undiscovered,
+              CASE WHEN p.state = 'mined' THEN 1 ELSE 0 END AS undiscovered,
               p.created_at AS created
          RETURN difficulty,
                 count(*) AS total_mined,
@@ -840,8 +1730,382 @@ pub async fn get_pool_monitoring(graph: &Graph) -> Result<Vec<PoolStats>, ApiErr
     Ok(stats)
 }
 
+// ── Short codes ─────────────────────────────────────────────────
+
+/// Return the puzzle's server short code, allocating one if it has none.
+pub async fn ensure_puzzle_short_code(graph: &Graph, hash: &str) -> Result<String, ApiError> {
+    ensure_short_code(graph, "Puzzle", "hash", hash).await
+}
+
+/// Return the share's server short code, allocating one if it has none.
+pub async fn ensure_share_short_code(graph: &Graph, share_id: &str) -> Result<String, ApiError> {
+    ensure_short_code(graph, "Share", "share_id", share_id).await
+}
+
+/// Try deterministic candidates (seeded by the node key) until one is free.
+/// Puzzles and shares share one namespace, since `/s/{code}` resolves
+/// either: a code is claimed by a `:ShortCode {code}` node that records its
+/// owner, and the uniqueness constraint on it decides races. Claims are
+/// idempotent, so a retry for the same node reclaims its own candidate.
+async fn ensure_short_code(
+    graph: &Graph,
+    label: &str,
+    key_prop: &str,
+    key: &str,
+) -> Result<String, ApiError> {
+    let cypher = format!(
+        "MATCH (n:{label} {{{key_prop}: $key}})
+         FOREACH (_ IN CASE WHEN n.short_code IS NULL THEN [1] ELSE [] END |
+             MERGE (c:ShortCode {{code: $code}})
+             ON CREATE SET c.label = $label, c.key = $key)
+         WITH n
+         OPTIONAL MATCH (c:ShortCode {{code: $code, label: $label, key: $key}})
+         FOREACH (_ IN CASE WHEN n.short_code IS NULL AND c IS NOT NULL THEN [1] ELSE [] END |
+             SET n.short_code = $code)
+         RETURN n.short_code AS short_code"
+    );
+
+    for attempt in 0..short_code::MAX_ATTEMPTS {
+        let candidate = short_code::candidate(key, attempt);
+        let q = query(&cypher)
+            .param("key", key)
+            .param("label", label)
+            .param("code", candidate.as_str());
+
+        let row = match graph.execute(q).await {
+            Ok(mut result) => result.next().await,
+            Err(e) => Err(e),
+        };
+        match row {
+            Ok(Some(row)) => {
+                if let Ok(code) = row.get::<String>("short_code") {
+                    return Ok(code);
+                }
+                // Candidate taken by another node: try the next one
+            }
+            Ok(None) => {
+                return Err(ApiError::NotFound(format!("{} {} not found", label, key)));
+            }
+            Err(neo4rs::Error::Neo4j(e))
+                if e.code() == "Neo.ClientError.Schema.ConstraintValidationFailed" => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    Err(ApiError::Internal(format!(
+        "no free short code for {} {} after {} attempts",
+        label,
+        key,
+        short_code::MAX_ATTEMPTS
+    )))
+}
+
+/// Allocate codes for up to `limit` puzzles and shares that have none.
+/// Returns (puzzles, shares) updated.
+pub async fn backfill_short_codes(graph: &Graph, limit: u64) -> Result<(u64, u64), ApiError> {
+    let mut puzzles = 0;
+    let mut result = graph
+        .execute(
+            query(
+                "MATCH (p:Puzzle) WHERE p.short_code IS NULL
+                 RETURN p.hash AS key LIMIT $limit",
+            )
+            .param("limit", limit as i64),
+        )
+        .await?;
+    let mut keys = Vec::new();
+    while let Some(row) = result.next().await? {
+        keys.push(row.get::<String>("key").unwrap_or_default());
+    }
+    for key in keys.iter().filter(|k| !k.is_empty()) {
+        ensure_puzzle_short_code(graph, key).await?;
+        puzzles += 1;
+    }
+
+    let mut shares = 0;
+    let mut result = graph
+        .execute(
+            query(
+                "MATCH (s:Share) WHERE s.short_code IS NULL
+                 RETURN s.share_id AS key LIMIT $limit",
+            )
+            .param("limit", limit as i64),
+        )
+        .await?;
+    let mut keys = Vec::new();
+    while let Some(row) = result.next().await? {
+        keys.push(row.get::<String>("key").unwrap_or_default());
+    }
+    for key in keys.iter().filter(|k| !k.is_empty()) {
+        ensure_share_short_code(graph, key).await?;
+        shares += 1;
+    }
+
+    Ok((puzzles, shares))
+}
+
+// ── Lifecycle ────────────────────────────────────────────────────
+
+/// Move a puzzle into `to` if its current state is one of `from`.
+///
+/// `from` is intersected with [`PuzzleState::allowed_from`], so no caller can
+/// perform an illegal transition. Returns the previous state, or `None` when
+/// the puzzle was not in an eligible state (or does not exist).
+///
+/// Only classic puzzles can be queued: the analyzer's solver does not know
+/// variant rules yet, so a queued variant puzzle would never leave the queue.
+pub async fn transition_puzzle(
+    graph: &Graph,
+    hash: &str,
+    from: &[PuzzleState],
+    to: PuzzleState,
+) -> Result<Option<PuzzleState>, ApiError> {
+    let eligible: Vec<PuzzleState> = from
+        .iter()
+        .copied()
+        .filter(|s| PuzzleState::can_transition(*s, to))
+        .collect();
+    if eligible.is_empty() {
+        return Ok(None);
+    }
+
+    let q = query(
+        "MATCH (p:Puzzle {hash: $hash})
+         WHERE p.state IN $from
+           AND ($to <> 'queued' OR p.variant = 'classic')
+         WITH p, p.state AS previous
+         SET p.state = $to, p.state_changed_at = datetime(), p.updated_at = datetime()
+         RETURN previous",
+    )
+    .param("hash", hash)
+    .param("from", PuzzleState::names(&eligible))
+    .param("to", to.as_str());
+
+    let mut result = graph.execute(q).await?;
+    if let Some(row) = result.next().await? {
+        let previous: String = row.get("previous").unwrap_or_default();
+        Ok(PuzzleState::parse(&previous))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Current lifecycle state of a puzzle, or `None` if it does not exist.
+pub async fn get_puzzle_state(graph: &Graph, hash: &str) -> Result<Option<String>, ApiError> {
+    let q = query("MATCH (p:Puzzle {hash: $hash}) RETURN p.state AS state").param("hash", hash);
+    let mut result = graph.execute(q).await?;
+    if let Some(row) = result.next().await? {
+        Ok(Some(row.get("state").unwrap_or_default()))
+    } else {
+        Ok(None)
+    }
+}
+
+pub async fn get_lifecycle_counts(graph: &Graph) -> Result<Vec<LifecycleCount>, ApiError> {
+    let q = query(
+        "MATCH (p:Puzzle)
+         RETURN COALESCE(p.state, 'unknown') AS state, count(p) AS count
+         ORDER BY state",
+    );
+    let mut result = graph.execute(q).await?;
+    let mut counts = Vec::new();
+    while let Some(row) = result.next().await? {
+        counts.push(LifecycleCount {
+            state: row.get("state").unwrap_or_default(),
+            count: row.get::<i64>("count").unwrap_or(0) as u64,
+        });
+    }
+    Ok(counts)
+}
+
+/// Puzzles in `state`, longest-waiting first.
+pub async fn list_puzzles_by_state(
+    graph: &Graph,
+    state: PuzzleState,
+    limit: u64,
+) -> Result<Vec<LifecyclePuzzle>, ApiError> {
+    let q = query(
+        "MATCH (p:Puzzle {state: $state})
+         RETURN p.hash AS puzzle_hash, p.state AS state, p.difficulty AS difficulty,
+                toString(p.state_changed_at) AS state_changed_at
+         ORDER BY p.state_changed_at ASC
+         LIMIT $limit",
+    )
+    .param("state", state.as_str())
+    .param("limit", limit as i64);
+
+    let mut result = graph.execute(q).await?;
+    let mut puzzles = Vec::new();
+    while let Some(row) = result.next().await? {
+        puzzles.push(LifecyclePuzzle {
+            puzzle_hash: row.get("puzzle_hash").unwrap_or_default(),
+            state: row.get("state").unwrap_or_default(),
+            difficulty: row.get("difficulty").unwrap_or_default(),
+            state_changed_at: row.get("state_changed_at").ok(),
+        });
+    }
+    Ok(puzzles)
+}
+
+// ── Reports ──────────────────────────────────────────────────────
+
+/// Create a Report linked to the puzzle, or return the id of the player's
+/// report on it that is still open. Writing `_lock` first serializes
+/// concurrent reports on the same puzzle so the MERGE cannot double-create.
+/// Returns `None` if the puzzle does not exist.
+pub async fn create_report(
+    graph: &Graph,
+    puzzle_hash: &str,
+    reason: &str,
+    comment: Option<&str>,
+    player_id: &str,
+) -> Result<Option<String>, ApiError> {
+    let id = Uuid::new_v4().to_string();
+    let q = query(
+        "MATCH (p:Puzzle {hash: $hash})
+         SET p._lock = true
+         REMOVE p._lock
+         MERGE (r:Report {player_id: $player, status: 'open'})-[:REPORTS]->(p)
+         ON CREATE SET r.id = $id,
+                       r.reason = $reason,
+                       r.comment = $comment,
+                       r.created_at = datetime()
+         RETURN r.id AS id",
+    )
+    .param("hash", puzzle_hash)
+    .param("id", id.as_str())
+    .param("reason", reason)
+    .param("comment", comment.unwrap_or(""))
+    .param("player", player_id);
+
+    let mut result = graph.execute(q).await?;
+    if let Some(row) = result.next().await? {
+        Ok(Some(row.get::<String>("id").unwrap_or(id)))
+    } else {
+        Ok(None)
+    }
+}
+
+pub async fn list_reports(
+    graph: &Graph,
+    status: &str,
+    limit: u64,
+    offset: u64,
+) -> Result<Vec<ReportDetail>, ApiError> {
+    let q = query(
+        "MATCH (r:Report {status: $status})-[:REPORTS]->(p:Puzzle)
+         WITH r, p ORDER BY r.created_at DESC SKIP $offset LIMIT $limit
+         OPTIONAL MATCH (o:Report {status: 'open'})-[:REPORTS]->(p)
+         WITH r, p, count(o) AS open_reports
+         RETURN r.id AS id, p.hash AS puzzle_hash, p.puzzle_string AS puzzle_string,
+                r.reason AS reason, r.comment AS comment, r.player_id AS player_id,
+                r.status AS status, r.resolution AS resolution,
+                toString(r.created_at) AS created_at,
+                toString(r.resolved_at) AS resolved_at,
+                open_reports
+         ORDER BY r.created_at DESC",
+    )
+    .param("status", status)
+    .param("limit", limit as i64)
+    .param("offset", offset as i64);
+
+    let mut reports = Vec::new();
+    let mut result = graph.execute(q).await?;
+    while let Some(row) = result.next().await? {
+        reports.push(row_to_report_detail(&row));
+    }
+    Ok(reports)
+}
+
+/// Close a report with a moderator note. Returns `None` if the report does not exist.
+pub async fn resolve_report(
+    graph: &Graph,
+    id: &str,
+    resolution: &str,
+) -> Result<Option<ReportDetail>, ApiError> {
+    let q = query(
+        "MATCH (r:Report {id: $id})-[:REPORTS]->(p:Puzzle)
+         SET r.status = 'resolved', r.resolution = $resolution, r.resolved_at = datetime()
+         WITH r, p
+         OPTIONAL MATCH (o:Report {status: 'open'})-[:REPORTS]->(p)
+         WITH r, p, count(o) AS open_reports
+         RETURN r.id AS id, p.hash AS puzzle_hash, p.puzzle_string AS puzzle_string,
+                r.reason AS reason, r.comment AS comment, r.player_id AS player_id,
+                r.status AS status, r.resolution AS resolution,
+                toString(r.created_at) AS created_at,
+                toString(r.resolved_at) AS resolved_at,
+                open_reports",
+    )
+    .param("id", id)
+    .param("resolution", resolution);
+
+    let mut result = graph.execute(q).await?;
+    if let Some(row) = result.next().await? {
+        Ok(Some(row_to_report_detail(&row)))
+    } else {
+        Ok(None)
+    }
+}
+
+fn row_to_report_detail(row: &neo4rs::Row) -> ReportDetail {
+    ReportDetail {
+        id: row.get("id").unwrap_or_default(),
+        puzzle_hash: row.get("puzzle_hash").unwrap_or_default(),
+        puzzle_string: row.get("puzzle_string").unwrap_or_default(),
+        reason: row.get("reason").unwrap_or_default(),
+        comment: row.get::<String>("comment").ok().filter(|s| !s.is_empty()),
+        player_id: row.get("player_id").unwrap_or_default(),
+        status: row.get("status").unwrap_or_default(),
+        resolution: row.get("resolution").ok(),
+        created_at: row.get("created_at").unwrap_or_default(),
+        resolved_at: row.get("resolved_at").ok(),
+        open_reports_for_puzzle: row.get::<i64>("open_reports").unwrap_or(0) as u64,
+    }
+}
+
+/// Retire a puzzle and close its open reports. Returns `None` if the puzzle does not exist.
+pub async fn retire_puzzle(
+    graph: &Graph,
+    hash: &str,
+    reason: &str,
+) -> Result<Option<RetirePuzzleResponse>, ApiError> {
+    let q = query(
+        "MATCH (p:Puzzle {hash: $hash})
+         SET p.state_changed_at = CASE WHEN p.state = 'retired'
+                                       THEN p.state_changed_at ELSE datetime() END,
+             p.state = 'retired', p.retired_reason = $reason,
+             p.retired_at = COALESCE(p.retired_at, datetime()), p.updated_at = datetime()
+         WITH p
+         OPTIONAL MATCH (r:Report {status: 'open'})-[:REPORTS]->(p)
+         SET r.status = 'resolved', r.resolution = 'retired: ' + $reason,
+             r.resolved_at = datetime()
+         RETURN p.hash AS puzzle_hash, count(r) AS resolved",
+    )
+    .param("hash", hash)
+    .param("reason", reason);
+
+    let mut result = graph.execute(q).await?;
+    if let Some(row) = result.next().await? {
+        Ok(Some(RetirePuzzleResponse {
+            puzzle_hash: row.get("puzzle_hash").unwrap_or_default(),
+            retired: true,
+            reports_resolved: row.get::<i64>("resolved").unwrap_or(0) as u64,
+        }))
+    } else {
+        Ok(None)
+    }
+}
+
 // ── Helpers ──────────────────────────────────────────────────────────
 
+/// Read a `variant` column, treating missing or unknown values as classic.
+fn row_variant(row: &neo4rs::Row) -> Variant {
+    row.get::<String>("variant")
+        .ok()
+        .and_then(|v| Variant::parse(&v))
+        .unwrap_or_default()
+}
+
 fn md5_hash(input: &str) -> u128 {
     // Simple FNV-like hash for puzzle dedup (not cryptographic, just for ID)
     let mut hash: u128 = 0xcbf29ce484222325;
@@ -1,8 +1,10 @@
+mod conditional;
 mod config;
 mod error;
 mod extractors;
 mod graph;
 mod models;
+mod packed;
 mod routes;
 mod services;
 mod state;
@@ -61,6 +63,7 @@ async fn run() -> anyhow::Result<()> {
         redis,
         config: config.clone(),
         galaxy_tx,
+        galaxy_cache: Default::default(),
     });
 
     let app = build_router(state);
@@ -82,6 +85,11 @@ fn build_router(state: Arc<AppState>) -> Router {
         // Results
         .route("/results", post(routes::results::submit_result))
         .route("/results/leaderboard", get(routes::results::leaderboard))
+        .route(
+            "/results/leaderboard/discoverers",
+            get(routes::results::top_discoverers),
+        )
+        .route("/results/{id}/replay", get(routes::results::replay))
         // Puzzles
         .route("/puzzles/random", get(routes::puzzles::get_random))
         .route("/puzzles/{hash}", get(routes::puzzles::get_by_hash))
@@ -93,14 +101,38 @@ fn build_router(state: Arc<AppState>) -> Router {
             "/puzzles/{hash}/techniques",
             get(routes::puzzles::get_techniques),
         )
+        .route("/puzzles/{hash}/path", get(routes::puzzles::get_path))
+        .route("/puzzles/{hash}/heatmap", get(routes::puzzles::get_heatmap))
+        .route(
+            "/puzzles/{hash}/solve-times",
+            get(routes::puzzles::get_solve_times),
+        )
+        .route(
+            "/puzzles/{hash}/reports",
+            post(routes::reports::create_report),
+        )
         // Galaxy
         .route("/galaxy/overview", get(routes::galaxy::overview))
+        .route("/galaxy/viewport", get(routes::galaxy::viewport))
         .route("/galaxy/cluster/{family}", get(routes::galaxy::cluster))
+        .route("/galaxy/communities", get(routes::galaxy::communities))
+        .route("/galaxy/communities/{id}", get(routes::galaxy::community))
         .route("/galaxy/neighbors/{hash}", get(routes::galaxy::neighbors))
+        .route("/galaxy/path", get(routes::galaxy::path))
         .route("/galaxy/stats", get(routes::galaxy::stats))
+        .route("/galaxy/timeline", get(routes::galaxy::timeline))
         .route("/galaxy/recent", get(routes::galaxy::recent))
         // Techniques
         .route("/techniques", get(routes::techniques::list_all))
+        .route("/techniques/graph", get(routes::techniques::graph))
+        .route(
+            "/techniques/prerequisites",
+            get(routes::techniques::prerequisite_graph),
+        )
+        .route(
+            "/techniques/{name}/prerequisites",
+            get(routes::techniques::prerequisites),
+        )
         .route(
             "/techniques/{name}/puzzles",
             get(routes::techniques::puzzles_by_technique),
@@ -122,6 +154,36 @@ fn build_router(state: Arc<AppState>) -> Router {
         .route(
             "/internal/puzzles/monitoring",
             get(routes::mining::pool_monitoring),
+        )
+        .route(
+            "/internal/puzzles/lifecycle",
+            get(routes::lifecycle::counts),
+        )
+        .route(
+            "/internal/puzzles/lifecycle/{state}",
+            get(routes::lifecycle::list_by_state),
+        )
+        .route(
+            "/internal/galaxy/cache",
+            get(routes::galaxy::cache_stats),
+        )
+        .route(
+            "/internal/short-codes/backfill",
+            post(routes::mining::backfill_short_codes),
+        )
+        // Moderation
+        .route("/admin/reports", get(routes::reports::list_reports))
+        .route(
+            "/admin/reports/{id}/resolve",
+            post(routes::reports::resolve_report),
+        )
+        .route(
+            "/admin/puzzles/{hash}/retire",
+            post(routes::reports::retire_puzzle),
+        )
+        .route(
+            "/admin/puzzles/{hash}/state",
+            post(routes::lifecycle::transition),
         );
 
     Router::new()
@@ -1,4 +1,6 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use ukodus_grid::Variant;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct GalaxyOverview {
@@ -13,6 +15,9 @@ pub struct GalaxyNode {
     pub short_code: Option<String>,
     pub difficulty: String,
     pub se_rating: f32,
+    /// True once the analyzer has replaced the client-reported rating.
+    #[serde(default)]
+    pub rating_verified: bool,
     pub play_count: u64,
     pub max_technique: Option<String>,
     pub techniques: Vec<String>,
@@ -27,6 +32,26 @@ pub struct GalaxyEdge {
     pub similarity: f64,
 }
 
+/// Galaxy cache activity in this API process since it started.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct GalaxyCacheStats {
+    /// Served a fresh entry
+    pub hits: u64,
+    /// Served a stale entry while it was rebuilt in the background
+    pub stale_hits: u64,
+    /// Found no usable entry
+    pub misses: u64,
+    /// Misses answered by another request's load instead of a new query
+    pub coalesced: u64,
+    /// Entries rebuilt from the graph
+    pub refreshes: u64,
+    pub refresh_errors: u64,
+    /// Generation bumps (immediate or debounced)
+    pub invalidations: u64,
+    /// Invalidations deferred by the debounce window
+    pub debounced_invalidations: u64,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct GalaxyStats {
     pub total_puzzles: u64,
@@ -37,8 +62,13 @@ pub struct GalaxyStats {
 
 #[derive(Debug, Deserialize)]
 pub struct ShareInput {
+    /// WASM-generated code; the server allocates its own `short_code`
     pub short_code: Option<String>,
+    #[serde(deserialize_with = "crate::models::grid::notation")]
     pub puzzle_string: String,
+    /// Rule set; defaults to classic
+    #[serde(default)]
+    pub variant: Variant,
     pub difficulty: String,
     pub se_rating: f32,
     pub platform: String,
@@ -58,7 +88,11 @@ pub struct ShareDetail {
     pub share_id: String,
     pub puzzle_hash: String,
     pub puzzle_string: String,
+    pub variant: Variant,
     pub short_code: Option<String>,
+    /// WASM-generated code supplied when the share was created
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_code: Option<String>,
     pub difficulty: String,
     pub se_rating: f32,
     pub platform: String,
@@ -70,4 +104,203 @@ pub struct ShareDetail {
 #[derive(Debug, Deserialize)]
 pub struct GalaxyQuery {
     pub limit: Option<u64>,
+    /// Lifecycle filter; defaults to all public states
+    pub state: Option<String>,
+    /// Each variant has its own galaxy; defaults to classic
+    pub variant: Option<Variant>,
+    /// Overview only: which puzzles fill the `limit`
+    pub sort: Option<OverviewSort>,
+    /// Overview only: restrict to one difficulty tier
+    pub difficulty: Option<String>,
+    /// Overview only: restrict to puzzles requiring this technique
+    /// (enum or display name)
+    pub technique: Option<String>,
+    /// Overview only: the galaxy as it stood at this RFC 3339 time
+    pub as_of: Option<String>,
+}
+
+/// How `/galaxy/overview` picks its nodes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverviewSort {
+    /// Most played first
+    #[default]
+    Plays,
+    /// Most recently discovered first
+    Recent,
+    /// Hardest first
+    Se,
+    /// Round-robin across (technique family, tier) strata, so rare kinds of
+    /// puzzle are represented; order within a stratum is by hash, which is
+    /// stable but unrelated to popularity
+    Sample,
+}
+
+impl OverviewSort {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            OverviewSort::Plays => "plays",
+            OverviewSort::Recent => "recent",
+            OverviewSort::Se => "se",
+            OverviewSort::Sample => "sample",
+        }
+    }
+}
+
+/// Everything that decides which nodes the overview returns.
+#[derive(Debug, Clone)]
+pub struct OverviewParams {
+    pub limit: u64,
+    pub states: Vec<String>,
+    pub variant: Variant,
+    pub sort: OverviewSort,
+    pub difficulty: Option<String>,
+    pub technique: Option<String>,
+    /// Only puzzles discovered and edges created by then
+    pub as_of: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TimelineQuery {
+    /// Lifecycle filter; defaults to all public states
+    pub state: Option<String>,
+    /// Each variant has its own galaxy; defaults to classic
+    pub variant: Option<Variant>,
+    pub bucket: Option<TimelineBucket>,
+}
+
+/// Width of one `/galaxy/timeline` bucket.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimelineBucket {
+    Hour,
+    #[default]
+    Day,
+    Week,
+    Month,
+}
+
+impl TimelineBucket {
+    /// Also the Cypher `datetime.truncate` unit
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TimelineBucket::Hour => "hour",
+            TimelineBucket::Day => "day",
+            TimelineBucket::Week => "week",
+            TimelineBucket::Month => "month",
+        }
+    }
+}
+
+/// How the galaxy grew: puzzles discovered and similarity edges created per
+/// bucket, oldest first. Empty buckets are omitted.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GalaxyTimeline {
+    pub bucket: TimelineBucket,
+    pub buckets: Vec<TimelineEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct TimelineEntry {
+    /// RFC 3339 start of the bucket
+    pub start: String,
+    pub nodes: u64,
+    pub edges: u64,
+    /// Running totals up to and including this bucket
+    pub total_nodes: u64,
+    pub total_edges: u64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ViewportQuery {
+    pub minx: f64,
+    pub miny: f64,
+    pub maxx: f64,
+    pub maxy: f64,
+    /// Current zoom scale; below the detail threshold nodes are aggregated
+    pub zoom: f64,
+    pub limit: Option<u64>,
+    /// Lifecycle filter; defaults to all public states
+    pub state: Option<String>,
+    /// Each variant has its own galaxy; defaults to classic
+    pub variant: Option<Variant>,
+}
+
+/// What is visible in one viewport: individual puzzles when zoomed in,
+/// aggregated `clusters` when zoomed out. Edges only join visible nodes.
+#[derive(Debug, Serialize, Clone)]
+pub struct GalaxyViewport {
+    pub detailed: bool,
+    pub nodes: Vec<GalaxyNode>,
+    pub clusters: Vec<GalaxyCentroid>,
+    pub edges: Vec<GalaxyEdge>,
+    /// True when the box held more puzzles than `limit`; the most played
+    /// ones were returned
+    pub truncated: bool,
+}
+
+/// Puzzles in one aggregation cell, drawn at their centroid.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct GalaxyCentroid {
+    pub x: f64,
+    pub y: f64,
+    pub count: u64,
+    pub play_count: u64,
+    /// Most common difficulty in the cell
+    pub difficulty: String,
+}
+
+/// A community of similar puzzles found by the analyzer (`Cluster` node).
+#[derive(Debug, Serialize, Clone)]
+pub struct Community {
+    pub id: String,
+    pub variant: Variant,
+    pub size: u64,
+    pub min_se_rating: f64,
+    pub max_se_rating: f64,
+    /// Most common hardest techniques among members, most common first
+    pub dominant_techniques: Vec<String>,
+    /// Centroid of the members' layout positions, once laid out
+    pub x: Option<f64>,
+    pub y: Option<f64>,
+    pub updated_at: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct CommunityDetail {
+    #[serde(flatten)]
+    pub community: Community,
+    pub nodes: Vec<GalaxyNode>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CommunityQuery {
+    pub limit: Option<u64>,
+    /// Each variant has its own galaxy; defaults to classic
+    pub variant: Option<Variant>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LearningPathQuery {
+    pub from: String,
+    pub to: String,
+}
+
+/// Stepping stones from one puzzle to a harder one, easiest first.
+#[derive(Debug, Serialize, Clone)]
+pub struct LearningPath {
+    pub from: String,
+    pub to: String,
+    pub steps: Vec<LearningStep>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct LearningStep {
+    pub puzzle_hash: String,
+    pub short_code: Option<String>,
+    pub difficulty: String,
+    pub se_rating: f64,
+    pub techniques: Vec<String>,
+    /// Techniques this step needs that the previous one did not
+    pub new_techniques: Vec<String>,
 }
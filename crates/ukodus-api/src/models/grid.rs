@@ -0,0 +1,17 @@
+//! Request-side handling of grid fields.
+
+use serde::{Deserialize, Deserializer};
+use serde_json::Value;
+
+/// Accept a grid field either as a string in any notation `ukodus_grid`
+/// understands or as a bare JSON 9x9 array, keeping it as text so the
+/// route can parse it and report errors against the field.
+pub fn notation<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    match Value::deserialize(deserializer)? {
+        Value::String(s) => Ok(s),
+        rows @ Value::Array(_) => Ok(rows.to_string()),
+        _ => Err(serde::de::Error::custom(
+            "expected a grid string or a 9x9 array",
+        )),
+    }
+}
@@ -0,0 +1,152 @@
+use serde::{Deserialize, Serialize};
+
+/// Where a puzzle is in its life, stored as `p.state`.
+///
+/// ```text
+/// mined ──> discovered ──> queued ──> analyzed
+///                            ^  │  ──> failed
+///                            └──┘ (re-analysis / retry)
+/// any state ──> retired
+/// ```
+///
+/// Player-submitted puzzles are created as `discovered`; mined puzzles start
+/// as `mined` and stay hidden until someone finishes a game on them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PuzzleState {
+    Mined,
+    Discovered,
+    Queued,
+    Analyzed,
+    Failed,
+    Retired,
+}
+
+impl PuzzleState {
+    pub const ALL: &'static [PuzzleState] = &[
+        PuzzleState::Mined,
+        PuzzleState::Discovered,
+        PuzzleState::Queued,
+        PuzzleState::Analyzed,
+        PuzzleState::Failed,
+        PuzzleState::Retired,
+    ];
+
+    /// States visible on public endpoints.
+    pub const PUBLIC: &'static [PuzzleState] = &[
+        PuzzleState::Discovered,
+        PuzzleState::Queued,
+        PuzzleState::Analyzed,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PuzzleState::Mined => "mined",
+            PuzzleState::Discovered => "discovered",
+            PuzzleState::Queued => "queued",
+            PuzzleState::Analyzed => "analyzed",
+            PuzzleState::Failed => "failed",
+            PuzzleState::Retired => "retired",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<PuzzleState> {
+        PuzzleState::ALL.iter().copied().find(|state| state.as_str() == s)
+    }
+
+    /// States a puzzle may move into `self` from.
+    pub fn allowed_from(self) -> &'static [PuzzleState] {
+        use PuzzleState::*;
+        match self {
+            Mined => &[],
+            Discovered => &[Mined],
+            Queued => &[Discovered, Analyzed, Failed],
+            Analyzed | Failed => &[Queued],
+            Retired => &[Mined, Discovered, Queued, Analyzed, Failed],
+        }
+    }
+
+    pub fn can_transition(from: PuzzleState, to: PuzzleState) -> bool {
+        to.allowed_from().contains(&from)
+    }
+
+    pub fn names(states: &[PuzzleState]) -> Vec<String> {
+        states.iter().map(|s| s.as_str().to_string()).collect()
+    }
+}
+
+/// Parse an optional `?state=` filter for a public endpoint.
+///
+/// `None` means all public states; hidden states are rejected.
+pub fn public_state_filter(state: Option<&str>) -> Result<Vec<String>, String> {
+    match state {
+        None => Ok(PuzzleState::names(PuzzleState::PUBLIC)),
+        Some(s) => match PuzzleState::parse(s) {
+            Some(state) if PuzzleState::PUBLIC.contains(&state) => Ok(vec![s.to_string()]),
+            _ => Err(format!(
+                "state must be one of: {}",
+                PuzzleState::names(PuzzleState::PUBLIC).join(", ")
+            )),
+        },
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TransitionInput {
+    pub state: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TransitionResponse {
+    pub puzzle_hash: String,
+    pub from: PuzzleState,
+    pub to: PuzzleState,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LifecycleQuery {
+    pub limit: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LifecycleCount {
+    pub state: String,
+    pub count: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LifecyclePuzzle {
+    pub puzzle_hash: String,
+    pub state: String,
+    pub difficulty: String,
+    pub state_changed_at: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transitions() {
+        use PuzzleState::*;
+        assert!(PuzzleState::can_transition(Mined, Discovered));
+        assert!(PuzzleState::can_transition(Discovered, Queued));
+        assert!(PuzzleState::can_transition(Queued, Analyzed));
+        assert!(PuzzleState::can_transition(Failed, Queued));
+        assert!(PuzzleState::can_transition(Analyzed, Retired));
+        assert!(!PuzzleState::can_transition(Mined, Analyzed));
+        assert!(!PuzzleState::can_transition(Retired, Queued));
+        assert!(!PuzzleState::can_transition(Analyzed, Discovered));
+        for state in PuzzleState::ALL {
+            assert_eq!(PuzzleState::parse(state.as_str()), Some(*state));
+        }
+    }
+
+    #[test]
+    fn test_public_state_filter() {
+        assert_eq!(public_state_filter(None).unwrap().len(), 3);
+        assert_eq!(public_state_filter(Some("analyzed")).unwrap(), vec!["analyzed"]);
+        assert!(public_state_filter(Some("mined")).is_err());
+        assert!(public_state_filter(Some("bogus")).is_err());
+    }
+}
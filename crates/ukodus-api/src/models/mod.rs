@@ -1,2 +1,6 @@
 pub mod galaxy;
+pub mod grid;
+pub mod lifecycle;
 pub mod puzzle;
+pub mod report;
+pub mod technique;
@@ -1,4 +1,6 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use ukodus_grid::Variant;
 
 /// A single move from the WASM move log (mirrors sudoku-wasm types)
 #[derive(Debug, Clone, Deserialize)]
@@ -17,12 +19,22 @@ pub enum MoveAction {
     Hint(u8),
     Undo(Option<u8>),
     Redo(Option<u8>),
+    /// Pencil-mark a candidate in the cell
+    NoteAdd(u8),
+    /// Remove a pencil-marked candidate from the cell
+    NoteRemove(u8),
+    /// Fill every empty cell with its possible candidates (`cell` is ignored)
+    AutoNotes,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct GameResultInput {
-    pub puzzle_hash: String,
+    #[serde(deserialize_with = "crate::models::grid::notation")]
     pub puzzle_string: String,
+    /// Rule set; defaults to classic
+    #[serde(default)]
+    pub variant: Variant,
+    /// WASM-generated code; the server allocates its own `short_code`
     pub short_code: Option<String>,
     pub difficulty: String,
     pub se_rating: f32,
@@ -46,9 +58,45 @@ pub struct GameResultInput {
 #[derive(Debug, Serialize)]
 pub struct GameResultResponse {
     pub id: String,
+    /// Server-derived identity of the played puzzle
+    pub puzzle_hash: String,
+    pub short_code: String,
     pub verified: bool,
     pub puzzle_is_new: bool,
     pub leaderboard_eligible: bool,
+    /// Share of other verified winners this result beat, for verified wins
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub faster_than_percent: Option<f64>,
+}
+
+/// A stored game's move log replayed against its puzzle. Replays are public
+/// by result id, so nothing identifying the player is included.
+#[derive(Debug, Serialize)]
+pub struct GameReplay {
+    pub result_id: String,
+    pub puzzle_hash: String,
+    pub puzzle_string: String,
+    pub variant: Variant,
+    pub result: String,
+    pub time_secs: u64,
+    pub verified: bool,
+    pub moves: Vec<ReplayMove>,
+}
+
+/// One move and the board it produced.
+#[derive(Debug, Serialize, PartialEq)]
+pub struct ReplayMove {
+    pub seq: u32,
+    /// Milliseconds since the game started
+    pub ms: u32,
+    pub cell: u8,
+    /// place, clear, hint, undo, redo, note_add, note_remove or auto_notes
+    pub action: &'static str,
+    pub value: Option<u8>,
+    /// Whether a placement matches the solution; `None` for other actions
+    pub correct: Option<bool>,
+    /// 81-character board after the move, `0` for empty cells
+    pub board: String,
 }
 
 #[derive(Debug, Serialize, Clone)]
@@ -57,13 +105,66 @@ pub struct PuzzleDetail {
     pub puzzle_string: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub solution_string: Option<String>,
+    pub variant: Variant,
     pub short_code: Option<String>,
     pub difficulty: String,
     pub se_rating: f32,
+    /// True once the analyzer has replaced the client-reported rating.
+    pub rating_verified: bool,
+    /// Lifecycle state, see [`crate::models::lifecycle::PuzzleState`]
+    pub state: String,
+    /// Observed difficulty from verified player results, once the analyzer
+    /// has enough samples.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub empirical: Option<EmpiricalDifficulty>,
+    /// First player to finish a game on this mined puzzle
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub discoverer: Option<Discoverer>,
     pub play_count: u64,
     pub avg_solve_time: f64,
     pub win_rate: f64,
     pub techniques: Vec<String>,
+    /// Used for `Last-Modified`; not part of the body
+    #[serde(skip)]
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+/// Empirical difficulty computed by the analyzer's `empirical-difficulty` job.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EmpiricalDifficulty {
+    pub samples: u64,
+    /// Solve times are null until someone has won the puzzle
+    pub p10_solve_time: Option<f64>,
+    pub median_solve_time: Option<f64>,
+    pub p90_solve_time: Option<f64>,
+    pub win_rate: f64,
+    pub mistake_rate: f64,
+    /// Median solve time after normalizing for each player's skill
+    pub normalized_solve_time: Option<f64>,
+    /// Tier whose puzzles are typically solved in a similar time
+    pub tier: Option<String>,
+    /// True when `tier` is far from the SE-rated difficulty
+    pub tier_mismatch: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Discoverer {
+    pub player_id: String,
+    pub player_tag: Option<String>,
+    pub discovered_at: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DiscovererEntry {
+    pub player_id: String,
+    pub player_tag: Option<String>,
+    pub discoveries: u64,
+    pub last_discovered_at: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DiscovererQuery {
+    pub limit: Option<u64>,
 }
 
 #[derive(Debug, Serialize)]
@@ -84,6 +185,8 @@ pub struct LeaderboardEntry {
 
 #[derive(Debug, Deserialize)]
 pub struct LeaderboardQuery {
+    /// Defaults to classic; ignored when `puzzle_hash` is given
+    pub variant: Option<Variant>,
     pub difficulty: Option<String>,
     pub puzzle_hash: Option<String>,
     pub limit: Option<u64>,
@@ -92,18 +195,37 @@ pub struct LeaderboardQuery {
 
 #[derive(Debug, Deserialize)]
 pub struct MinedPuzzleInput {
-    pub puzzle_hash: String,
+    #[serde(deserialize_with = "crate::models::grid::notation")]
     pub puzzle_string: String,
+    #[serde(deserialize_with = "crate::models::grid::notation")]
     pub solution_string: String,
+    /// Rule set; defaults to classic
+    #[serde(default)]
+    pub variant: Variant,
     pub difficulty: String,
     pub se_rating: f32,
+    /// WASM-generated code; the server allocates its own `short_code`
     pub short_code: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
 pub struct MinedPuzzleResponse {
+    /// Server-derived identity of the mined puzzle
+    pub puzzle_hash: String,
     pub accepted: bool,
     pub duplicate: bool,
+    pub short_code: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ShortCodeBackfillQuery {
+    pub limit: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ShortCodeBackfillResponse {
+    pub puzzles: u64,
+    pub shares: u64,
 }
 
 #[derive(Debug, Deserialize)]
@@ -135,3 +257,82 @@ pub struct PoolMonitoringResponse {
 pub struct PoolInventoryResponse {
     pub counts: Vec<PoolCount>,
 }
+
+/// Where players struggle on a puzzle, aggregated from stored move logs.
+#[derive(Debug, Serialize)]
+pub struct PuzzleHeatmap {
+    pub puzzle_hash: String,
+    /// Number of games with a move log that went into the aggregate
+    pub games: u64,
+    /// All 81 cells in row-major order
+    pub cells: Vec<CellHeat>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CellHeat {
+    pub cell: u8,
+    pub row: u8,
+    pub col: u8,
+    pub given: bool,
+    /// Wrong digits placed in this cell across all games
+    pub mistakes: u32,
+    pub hints: u32,
+    /// Games in which the cell was correctly filled (including by hint)
+    pub fills: u32,
+    /// Median game clock at the first correct fill
+    pub median_time_to_fill_ms: Option<u32>,
+    /// Solving-path step that places this cell, once analyzed
+    pub path_step: Option<u32>,
+    /// Technique enum name of that step
+    pub technique: Option<String>,
+}
+
+/// Distribution of verified winning solve times for a puzzle.
+#[derive(Debug, Serialize)]
+pub struct SolveTimeDistribution {
+    pub puzzle_hash: String,
+    /// Verified wins the distribution is based on
+    pub samples: u64,
+    pub p10_secs: Option<f64>,
+    pub p50_secs: Option<f64>,
+    pub p90_secs: Option<f64>,
+    pub buckets: Vec<HistogramBucket>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HistogramBucket {
+    pub min_secs: u64,
+    /// Exclusive upper bound; `None` for the open-ended last bucket
+    pub max_secs: Option<u64>,
+    pub count: u64,
+}
+
+/// Ordered logical solution of a puzzle, as found by the analyzer.
+#[derive(Debug, Serialize)]
+pub struct PuzzlePath {
+    pub puzzle_hash: String,
+    pub max_se_rating: f32,
+    pub steps: Vec<PathStep>,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct PathStep {
+    pub index: u32,
+    /// Technique enum name, e.g. "HiddenSingle"
+    pub technique: String,
+    pub display_name: String,
+    /// SE rating of this step's technique, for plotting the difficulty curve
+    pub se_rating: f32,
+    pub row: u8,
+    pub col: u8,
+    pub action: StepAction,
+    /// The placed value, or the candidates eliminated from the cell
+    pub values: Vec<u8>,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum StepAction {
+    Place,
+    Eliminate,
+}
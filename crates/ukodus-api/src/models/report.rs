@@ -0,0 +1,54 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize)]
+pub struct ReportInput {
+    pub reason: String,
+    pub comment: Option<String>,
+    pub player_id: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReportResponse {
+    pub id: String,
+    pub status: String,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ReportDetail {
+    pub id: String,
+    pub puzzle_hash: String,
+    pub puzzle_string: String,
+    pub reason: String,
+    pub comment: Option<String>,
+    pub player_id: String,
+    pub status: String,
+    pub resolution: Option<String>,
+    pub created_at: String,
+    pub resolved_at: Option<String>,
+    /// Open reports against the same puzzle, including this one
+    pub open_reports_for_puzzle: u64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReportListQuery {
+    pub status: Option<String>,
+    pub limit: Option<u64>,
+    pub offset: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ResolveReportInput {
+    pub resolution: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RetirePuzzleInput {
+    pub reason: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RetirePuzzleResponse {
+    pub puzzle_hash: String,
+    pub retired: bool,
+    pub reports_resolved: u64,
+}
@@ -0,0 +1,63 @@
+use serde::{Deserialize, Serialize};
+
+/// Techniques and the `CO_OCCURS_WITH` edges between them.
+#[derive(Debug, Serialize, Clone)]
+pub struct TechniqueGraph {
+    pub nodes: Vec<TechniqueNode>,
+    pub edges: Vec<TechniqueEdge>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct TechniqueNode {
+    pub name: String,
+    pub display_name: Option<String>,
+    pub family: Option<String>,
+    pub se_rating: Option<f64>,
+    pub puzzle_count: u64,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct TechniqueEdge {
+    pub source: String,
+    pub target: String,
+    /// Puzzles requiring both techniques
+    pub count: u64,
+    /// Observed co-occurrence over what independence would predict
+    pub lift: f64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TechniqueGraphQuery {
+    pub min_count: Option<u64>,
+    pub min_lift: Option<f64>,
+}
+
+/// The curated `PREREQUISITE_OF` DAG.
+#[derive(Debug, Serialize, Clone)]
+pub struct PrerequisiteGraph {
+    pub nodes: Vec<TechniqueNode>,
+    pub edges: Vec<PrerequisiteEdge>,
+}
+
+/// `prerequisite` is worth knowing before `technique`.
+#[derive(Debug, Serialize, Clone)]
+pub struct PrerequisiteEdge {
+    pub prerequisite: String,
+    pub technique: String,
+}
+
+/// Everything to learn before one technique, in a valid learning order.
+#[derive(Debug, Serialize, Clone)]
+pub struct TechniquePrerequisites {
+    pub technique: String,
+    pub prerequisites: Vec<Prerequisite>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct Prerequisite {
+    pub name: String,
+    pub display_name: Option<String>,
+    pub se_rating: Option<f64>,
+    /// Fewest `PREREQUISITE_OF` hops to the technique; 1 is a direct prerequisite
+    pub distance: u64,
+}
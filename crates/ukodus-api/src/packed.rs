@@ -0,0 +1,341 @@
+//! Compact columnar encoding of galaxy payloads, served as MessagePack to
+//! clients that send `Accept: application/msgpack`.
+//!
+//! Each field is one column, so its name appears once instead of once per
+//! node. Every string (hashes, short codes, difficulties, technique names)
+//! is interned in `strings` and referenced by index, and 81-digit puzzle
+//! strings are packed two digits per byte.
+
+use std::collections::HashMap;
+
+use axum::http::header::ACCEPT;
+use axum::http::HeaderMap;
+use serde::{Deserialize, Serialize};
+
+use crate::models::galaxy::{GalaxyEdge, GalaxyNode, GalaxyOverview, GalaxyViewport};
+
+pub const MSGPACK: &str = "application/msgpack";
+const MSGPACK_LEGACY: &str = "application/x-msgpack";
+pub const FORMAT_VERSION: u32 = 1;
+/// Bytes per packed puzzle string: 81 digits, two per byte.
+pub const PUZZLE_BYTES: usize = 41;
+
+/// True when the `Accept` header prefers MessagePack over JSON: its q-value
+/// must be higher than JSON's, or equal when JSON is only matched by a
+/// wildcard. JSON stays the default otherwise.
+pub fn wants_msgpack(headers: &HeaderMap) -> bool {
+    let Some(accept) = headers.get(ACCEPT).and_then(|v| v.to_str().ok()) else {
+        return false;
+    };
+    let mut msgpack: Option<f32> = None;
+    let mut json: Option<f32> = None;
+    let mut wildcard: Option<f32> = None;
+    for range in accept.split(',') {
+        let mut parts = range.split(';').map(str::trim);
+        let media = parts.next().unwrap_or_default();
+        let q = parts
+            .find_map(|p| p.strip_prefix("q="))
+            .map_or(Some(1.0), |q| q.parse::<f32>().ok())
+            .unwrap_or(0.0);
+        let slot =
+            if media.eq_ignore_ascii_case(MSGPACK) || media.eq_ignore_ascii_case(MSGPACK_LEGACY) {
+                &mut msgpack
+            } else if media.eq_ignore_ascii_case("application/json") {
+                &mut json
+            } else if media == "*/*" || media.eq_ignore_ascii_case("application/*") {
+                &mut wildcard
+            } else {
+                continue;
+            };
+        *slot = Some(slot.map_or(q, |prev| prev.max(q)));
+    }
+    match (msgpack, json) {
+        (Some(m), _) if m <= 0.0 => false,
+        (Some(m), Some(j)) => m > j,
+        (Some(m), None) => m >= wildcard.unwrap_or(0.0),
+        (None, _) => false,
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct PackedGalaxy {
+    pub version: u32,
+    pub strings: Vec<String>,
+    pub nodes: PackedNodes,
+    pub edges: PackedEdges,
+    pub clusters: PackedClusters,
+    /// Viewport responses only
+    pub detailed: Option<bool>,
+    pub truncated: Option<bool>,
+}
+
+/// One entry per node in every column. `u32` values index `strings`.
+#[derive(Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct PackedNodes {
+    pub puzzle_hash: Vec<u32>,
+    /// `PUZZLE_BYTES` per node, high nibble first; 0xFF bytes when the node
+    /// has no (valid) puzzle string
+    #[serde(with = "serde_bytes")]
+    pub puzzle: Vec<u8>,
+    pub short_code: Vec<Option<u32>>,
+    pub difficulty: Vec<u32>,
+    pub se_rating: Vec<f32>,
+    pub rating_verified: Vec<bool>,
+    pub play_count: Vec<u64>,
+    pub max_technique: Vec<Option<u32>>,
+    pub techniques: Vec<Vec<u32>>,
+    pub x: Vec<Option<f32>>,
+    pub y: Vec<Option<f32>>,
+}
+
+/// Edge endpoints are puzzle hashes, as indices into `strings`.
+#[derive(Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct PackedEdges {
+    pub source: Vec<u32>,
+    pub target: Vec<u32>,
+    pub similarity: Vec<f32>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct PackedClusters {
+    pub x: Vec<f32>,
+    pub y: Vec<f32>,
+    pub count: Vec<u64>,
+    pub play_count: Vec<u64>,
+    pub difficulty: Vec<u32>,
+}
+
+impl PackedGalaxy {
+    pub fn from_nodes(nodes: &[GalaxyNode]) -> Self {
+        Self::build(nodes, &[])
+    }
+
+    pub fn from_overview(overview: &GalaxyOverview) -> Self {
+        Self::build(&overview.nodes, &overview.edges)
+    }
+
+    pub fn from_viewport(view: &GalaxyViewport) -> Self {
+        let mut packed = Self::build(&view.nodes, &view.edges);
+        let mut strings = Interner::resume(std::mem::take(&mut packed.strings));
+        for c in &view.clusters {
+            packed.clusters.x.push(c.x as f32);
+            packed.clusters.y.push(c.y as f32);
+            packed.clusters.count.push(c.count);
+            packed.clusters.play_count.push(c.play_count);
+            packed
+                .clusters
+                .difficulty
+                .push(strings.intern(&c.difficulty));
+        }
+        packed.strings = strings.strings;
+        packed.detailed = Some(view.detailed);
+        packed.truncated = Some(view.truncated);
+        packed
+    }
+
+    pub fn to_msgpack(&self) -> Result<Vec<u8>, rmp_serde::encode::Error> {
+        rmp_serde::to_vec_named(self)
+    }
+
+    fn build(nodes: &[GalaxyNode], edges: &[GalaxyEdge]) -> Self {
+        let mut strings = Interner::default();
+        let mut n = PackedNodes {
+            puzzle: Vec::with_capacity(nodes.len() * PUZZLE_BYTES),
+            ..Default::default()
+        };
+        for node in nodes {
+            n.puzzle_hash.push(strings.intern(&node.puzzle_hash));
+            n.puzzle.extend(
+                node.puzzle_string
+                    .as_deref()
+                    .and_then(pack_digits)
+                    .unwrap_or([0xFF; PUZZLE_BYTES]),
+            );
+            n.short_code
+                .push(node.short_code.as_deref().map(|c| strings.intern(c)));
+            n.difficulty.push(strings.intern(&node.difficulty));
+            n.se_rating.push(node.se_rating);
+            n.rating_verified.push(node.rating_verified);
+            n.play_count.push(node.play_count);
+            n.max_technique
+                .push(node.max_technique.as_deref().map(|t| strings.intern(t)));
+            n.techniques
+                .push(node.techniques.iter().map(|t| strings.intern(t)).collect());
+            n.x.push(node.x.map(|x| x as f32));
+            n.y.push(node.y.map(|y| y as f32));
+        }
+
+        let mut e = PackedEdges::default();
+        for edge in edges {
+            e.source.push(strings.intern(&edge.source));
+            e.target.push(strings.intern(&edge.target));
+            e.similarity.push(edge.similarity as f32);
+        }
+
+        PackedGalaxy {
+            version: FORMAT_VERSION,
+            strings: strings.strings,
+            nodes: n,
+            edges: e,
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Default)]
+struct Interner {
+    strings: Vec<String>,
+    index: HashMap<String, u32>,
+}
+
+impl Interner {
+    fn resume(strings: Vec<String>) -> Self {
+        let index = strings
+            .iter()
+            .enumerate()
+            .map(|(i, s)| (s.clone(), i as u32))
+            .collect();
+        Self { strings, index }
+    }
+
+    fn intern(&mut self, s: &str) -> u32 {
+        if let Some(&i) = self.index.get(s) {
+            return i;
+        }
+        let i = self.strings.len() as u32;
+        self.strings.push(s.to_string());
+        self.index.insert(s.to_string(), i);
+        i
+    }
+}
+
+/// Pack 81 digits two per byte, or `None` if `s` is not 81 digits.
+pub fn pack_digits(s: &str) -> Option<[u8; PUZZLE_BYTES]> {
+    let bytes = s.as_bytes();
+    if bytes.len() != 81 || !bytes.iter().all(u8::is_ascii_digit) {
+        return None;
+    }
+    let mut packed = [0u8; PUZZLE_BYTES];
+    for (i, b) in bytes.iter().enumerate() {
+        let digit = b - b'0';
+        packed[i / 2] |= if i % 2 == 0 { digit << 4 } else { digit };
+    }
+    Some(packed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    const PUZZLE: &str =
+        "530070000600195000098000060800060003400803001700020006060000280000419005000080079";
+
+    /// Inverse of [`pack_digits`]; `None` for the 0xFF "no puzzle" marker.
+    fn unpack_digits(packed: &[u8]) -> Option<String> {
+        if packed.len() != PUZZLE_BYTES || packed.iter().all(|&b| b == 0xFF) {
+            return None;
+        }
+        Some(
+            (0..81)
+                .map(|i| {
+                    let byte = packed[i / 2];
+                    let digit = if i % 2 == 0 { byte >> 4 } else { byte & 0x0F };
+                    (b'0' + digit) as char
+                })
+                .collect(),
+        )
+    }
+
+    fn node(hash: &str, techniques: &[&str]) -> GalaxyNode {
+        GalaxyNode {
+            puzzle_hash: hash.to_string(),
+            puzzle_string: Some(PUZZLE.to_string()),
+            short_code: None,
+            difficulty: "Easy".to_string(),
+            se_rating: 2.3,
+            rating_verified: true,
+            play_count: 12,
+            max_technique: techniques.last().map(|t| t.to_string()),
+            techniques: techniques.iter().map(|t| t.to_string()).collect(),
+            x: Some(1.5),
+            y: None,
+        }
+    }
+
+    #[test]
+    fn test_pack_digits_round_trip() {
+        let packed = pack_digits(PUZZLE).unwrap();
+        assert_eq!(unpack_digits(&packed).as_deref(), Some(PUZZLE));
+        assert_eq!(unpack_digits(&[0xFF; PUZZLE_BYTES]), None);
+        assert!(pack_digits("123").is_none());
+        assert!(pack_digits(&PUZZLE.replace('0', ".")).is_none());
+    }
+
+    #[test]
+    fn test_packed_overview_interns_and_shrinks() {
+        let nodes: Vec<GalaxyNode> = (0..200)
+            .map(|i| {
+                node(
+                    &format!("{i:032x}"),
+                    &["NakedSingle", "HiddenSingle", "XWing"],
+                )
+            })
+            .collect();
+        let overview = GalaxyOverview {
+            edges: vec![GalaxyEdge {
+                source: nodes[0].puzzle_hash.clone(),
+                target: nodes[1].puzzle_hash.clone(),
+                similarity: 0.75,
+            }],
+            nodes,
+        };
+        let packed = PackedGalaxy::from_overview(&overview);
+
+        // 200 hashes + "Easy" + three technique names
+        assert_eq!(packed.strings.len(), 204);
+        assert_eq!(packed.nodes.techniques[7].len(), 3);
+        assert_eq!(
+            packed.nodes.max_technique[7],
+            Some(packed.nodes.techniques[7][2])
+        );
+        assert_eq!(packed.edges.source, vec![packed.nodes.puzzle_hash[0]]);
+        assert_eq!(
+            unpack_digits(&packed.nodes.puzzle[PUZZLE_BYTES..2 * PUZZLE_BYTES]).as_deref(),
+            Some(PUZZLE)
+        );
+
+        let msgpack = packed.to_msgpack().unwrap();
+        let json = serde_json::to_vec(&overview).unwrap();
+        assert!(
+            msgpack.len() * 3 < json.len(),
+            "{} vs {}",
+            msgpack.len(),
+            json.len()
+        );
+        assert_eq!(
+            rmp_serde::from_slice::<PackedGalaxy>(&msgpack).unwrap(),
+            packed
+        );
+    }
+
+    #[test]
+    fn test_wants_msgpack() {
+        let accept = |v: &'static str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(ACCEPT, HeaderValue::from_static(v));
+            wants_msgpack(&headers)
+        };
+        assert!(!wants_msgpack(&HeaderMap::new()));
+        assert!(!accept("application/json"));
+        assert!(!accept("*/*"));
+        assert!(accept("application/msgpack"));
+        assert!(accept("application/json;q=0.5, application/x-msgpack"));
+        assert!(!accept("application/msgpack;q=0"));
+        assert!(!accept("application/json, application/msgpack;q=0.1"));
+        assert!(!accept("application/json, application/msgpack"));
+        assert!(accept("application/msgpack, application/json;q=0.5"));
+        assert!(accept("application/msgpack, */*"));
+        assert!(!accept("application/msgpack;q=0.5, */*"));
+    }
+}
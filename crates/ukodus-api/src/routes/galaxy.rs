@@ -1,50 +1,319 @@
 use axum::extract::{Path, Query, State};
+use axum::http::HeaderMap;
+use axum::response::Response;
 use axum::Json;
+use std::collections::HashMap;
 use std::sync::Arc;
+use ukodus_grid::{tier_index, DIFFICULTY_TIERS};
 
-use crate::error::ApiResult;
+use crate::conditional::{
+    self, OVERVIEW_CACHE_CONTROL, REVALIDATE_CACHE_CONTROL, STATS_CACHE_CONTROL,
+};
+use crate::error::{ApiError, ApiResult, FieldError};
+use crate::extractors::ApiKeyAuth;
 use crate::graph::queries;
-use crate::models::galaxy::{GalaxyNode, GalaxyOverview, GalaxyQuery, GalaxyStats};
-use crate::services::galaxy_service;
+use crate::models::galaxy::{
+    Community, CommunityDetail, CommunityQuery, GalaxyCacheStats, GalaxyQuery, GalaxyViewport,
+    LearningPath, LearningPathQuery, OverviewParams, TimelineQuery, ViewportQuery,
+};
+use crate::models::lifecycle::public_state_filter;
+use crate::packed::PackedGalaxy;
+use crate::services::learning_path::{self, Candidate};
+use crate::services::{galaxy_service, timeline, viewport};
 use crate::state::AppState;
 
 pub async fn overview(
     State(state): State<Arc<AppState>>,
     Query(params): Query<GalaxyQuery>,
-) -> ApiResult<Json<GalaxyOverview>> {
-    let limit = params.limit.unwrap_or(500).min(2000);
-    let overview = galaxy_service::get_cached_overview(&state, limit).await?;
-    Ok(Json(overview))
+    headers: HeaderMap,
+) -> ApiResult<Response> {
+    // Filters are part of the cache key, so unknown values are rejected
+    // rather than each getting an (empty) entry of their own
+    let difficulty = params.difficulty.filter(|d| !d.is_empty());
+    let mut technique = params.technique.filter(|t| !t.is_empty());
+    let mut errors = Vec::new();
+    if let Some(d) = &difficulty {
+        if tier_index(d).is_none() {
+            let names: Vec<&str> = DIFFICULTY_TIERS.iter().map(|(name, ..)| *name).collect();
+            errors.push(FieldError::new(
+                "difficulty",
+                format!("must be one of: {}", names.join(", ")),
+            ));
+        }
+    }
+    if let Some(t) = &technique {
+        // Key display names on the enum name so both spellings share an entry
+        match queries::resolve_technique_name(state.graph.inner(), t).await? {
+            Some(name) => technique = Some(name),
+            None => errors.push(FieldError::new("technique", "unknown technique")),
+        }
+    }
+    if !errors.is_empty() {
+        return Err(ApiError::Validation(errors));
+    }
+
+    let overview_params = OverviewParams {
+        limit: params.limit.unwrap_or(500).min(2000),
+        states: public_state_filter(params.state.as_deref()).map_err(ApiError::BadRequest)?,
+        variant: params.variant.unwrap_or_default(),
+        sort: params.sort.unwrap_or_default(),
+        difficulty,
+        technique,
+        as_of: params
+            .as_of
+            .as_deref()
+            .map(timeline::parse_as_of)
+            .transpose()?
+            .map(timeline::quantize_as_of),
+    };
+    let overview = galaxy_service::get_cached_overview(&state, &overview_params).await?;
+    conditional::negotiated_response(
+        &headers,
+        &overview,
+        PackedGalaxy::from_overview,
+        OVERVIEW_CACHE_CONTROL,
+    )
+}
+
+/// Nodes inside a box of layout coordinates, or cell centroids when zoomed
+/// out far enough that individual puzzles would be sub-pixel.
+pub async fn viewport(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<ViewportQuery>,
+    headers: HeaderMap,
+) -> ApiResult<Response> {
+    let errors = viewport::validate(&params);
+    if !errors.is_empty() {
+        return Err(ApiError::Validation(errors));
+    }
+    let states = public_state_filter(params.state.as_deref()).map_err(ApiError::BadRequest)?;
+    let variant = params.variant.unwrap_or_default();
+    let graph = state.graph.inner();
+
+    let view = if viewport::is_detailed(params.zoom) {
+        let limit = params.limit.unwrap_or(2000).min(5000);
+        let (nodes, truncated) =
+            queries::get_viewport_nodes(graph, &params, limit, &states, variant).await?;
+        let hashes: Vec<String> = nodes.iter().map(|n| n.puzzle_hash.clone()).collect();
+        let edges = if hashes.len() > 1 {
+            queries::get_edges_for_hashes(graph, &hashes, None).await?
+        } else {
+            Vec::new()
+        };
+        GalaxyViewport {
+            detailed: true,
+            nodes,
+            clusters: Vec::new(),
+            edges,
+            truncated,
+        }
+    } else {
+        let cell = viewport::cell_size(params.zoom);
+        let rows = queries::get_viewport_cells(graph, &params, cell, &states, variant).await?;
+        GalaxyViewport {
+            detailed: false,
+            nodes: Vec::new(),
+            clusters: viewport::merge_cells(rows),
+            edges: Vec::new(),
+            truncated: false,
+        }
+    };
+    conditional::negotiated_response(
+        &headers,
+        &view,
+        PackedGalaxy::from_viewport,
+        OVERVIEW_CACHE_CONTROL,
+    )
 }
 
 pub async fn cluster(
     State(state): State<Arc<AppState>>,
     Path(family): Path<String>,
-) -> ApiResult<Json<Vec<GalaxyNode>>> {
-    let nodes = queries::get_galaxy_cluster(state.graph.inner(), &family).await?;
-    Ok(Json(nodes))
+    Query(params): Query<GalaxyQuery>,
+    headers: HeaderMap,
+) -> ApiResult<Response> {
+    let states = public_state_filter(params.state.as_deref()).map_err(ApiError::BadRequest)?;
+    let variant = params.variant.unwrap_or_default();
+    let nodes = queries::get_galaxy_cluster(state.graph.inner(), &family, &states, variant).await?;
+    conditional::negotiated_response(
+        &headers,
+        &nodes,
+        |n| PackedGalaxy::from_nodes(n),
+        REVALIDATE_CACHE_CONTROL,
+    )
+}
+
+pub async fn communities(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<CommunityQuery>,
+) -> ApiResult<Json<Vec<Community>>> {
+    let limit = params.limit.unwrap_or(100).min(1000);
+    let variant = params.variant.unwrap_or_default();
+    let communities = queries::get_communities(state.graph.inner(), variant, limit).await?;
+    Ok(Json(communities))
+}
+
+pub async fn community(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Query(params): Query<CommunityQuery>,
+) -> ApiResult<Json<CommunityDetail>> {
+    let limit = params.limit.unwrap_or(200).min(2000);
+    queries::get_community(state.graph.inner(), &id, limit)
+        .await?
+        .map(Json)
+        .ok_or_else(|| ApiError::NotFound(format!("community {id} not found")))
+}
+
+/// Most stepping stones a learning-path search considers.
+const PATH_CANDIDATES: u64 = 5000;
+
+/// A graded route from `from` to a harder `to` through similar puzzles.
+pub async fn path(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<LearningPathQuery>,
+) -> ApiResult<Json<LearningPath>> {
+    let graph = state.graph.inner();
+    let (from, variant) = queries::get_path_puzzle(graph, &params.from)
+        .await?
+        .ok_or_else(|| ApiError::NotFound(format!("puzzle {} not found", params.from)))?;
+    let (to, to_variant) = queries::get_path_puzzle(graph, &params.to)
+        .await?
+        .ok_or_else(|| ApiError::NotFound(format!("puzzle {} not found", params.to)))?;
+    if to_variant != variant {
+        return Err(ApiError::invalid("to", "must be the same variant as from"));
+    }
+    if to.se_rating < from.se_rating {
+        return Err(ApiError::invalid(
+            "to",
+            "must be rated at least as high as from",
+        ));
+    }
+
+    // The search runs over [from, stepping stones..., to]
+    let mut steps = queries::get_path_candidates(
+        graph,
+        variant,
+        from.se_rating,
+        to.se_rating,
+        PATH_CANDIDATES,
+    )
+    .await?;
+    steps.retain(|s| s.puzzle_hash != from.puzzle_hash && s.puzzle_hash != to.puzzle_hash);
+    steps.insert(0, from);
+    if params.to != params.from {
+        steps.push(to);
+    }
+    let hashes: Vec<String> = steps.iter().map(|s| s.puzzle_hash.clone()).collect();
+    let index: HashMap<&str, usize> = hashes
+        .iter()
+        .enumerate()
+        .map(|(i, h)| (h.as_str(), i))
+        .collect();
+    let edges: Vec<(usize, usize)> = queries::get_edges_for_hashes(graph, &hashes, None)
+        .await?
+        .iter()
+        .filter_map(|e| {
+            Some((
+                *index.get(e.source.as_str())?,
+                *index.get(e.target.as_str())?,
+            ))
+        })
+        .collect();
+
+    let candidates: Vec<Candidate> = steps
+        .iter()
+        .map(|s| Candidate {
+            se_rating: s.se_rating,
+            techniques: s.techniques.iter().cloned().collect(),
+        })
+        .collect();
+    let route = learning_path::find_path(&candidates, &edges, 0, steps.len() - 1)
+        .ok_or_else(|| ApiError::NotFound("no climbing path between these puzzles".into()))?;
+
+    let mut path_steps = Vec::with_capacity(route.len());
+    for (n, &i) in route.iter().enumerate() {
+        let mut step = steps[i].clone();
+        if n > 0 {
+            step.new_techniques =
+                learning_path::new_techniques(&candidates[route[n - 1]], &candidates[i]);
+        }
+        path_steps.push(step);
+    }
+
+    Ok(Json(LearningPath {
+        from: params.from,
+        to: params.to,
+        steps: path_steps,
+    }))
 }
 
 pub async fn neighbors(
     State(state): State<Arc<AppState>>,
     Path(hash): Path<String>,
-) -> ApiResult<Json<GalaxyOverview>> {
+    headers: HeaderMap,
+) -> ApiResult<Response> {
     let overview = queries::get_galaxy_neighbors(state.graph.inner(), &hash).await?;
-    Ok(Json(overview))
+    conditional::negotiated_response(
+        &headers,
+        &overview,
+        PackedGalaxy::from_overview,
+        REVALIDATE_CACHE_CONTROL,
+    )
 }
 
 pub async fn stats(
     State(state): State<Arc<AppState>>,
-) -> ApiResult<Json<GalaxyStats>> {
-    let stats = galaxy_service::get_cached_stats(&state).await?;
-    Ok(Json(stats))
+    Query(params): Query<GalaxyQuery>,
+    headers: HeaderMap,
+) -> ApiResult<Response> {
+    let stats =
+        galaxy_service::get_cached_stats(&state, params.variant.unwrap_or_default()).await?;
+    conditional::json_response(&headers, &stats, STATS_CACHE_CONTROL, None)
+}
+
+/// Puzzle and edge counts per time bucket, for animating the galaxy's growth
+/// together with `/galaxy/overview?as_of=`.
+pub async fn timeline(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<TimelineQuery>,
+    headers: HeaderMap,
+) -> ApiResult<Response> {
+    let states = public_state_filter(params.state.as_deref()).map_err(ApiError::BadRequest)?;
+    let timeline = galaxy_service::get_cached_timeline(
+        &state,
+        params.bucket.unwrap_or_default(),
+        &states,
+        params.variant.unwrap_or_default(),
+    )
+    .await?;
+    conditional::json_response(&headers, &timeline, OVERVIEW_CACHE_CONTROL, None)
+}
+
+/// Hit, miss and refresh counts of this process's galaxy cache.
+pub async fn cache_stats(
+    _auth: ApiKeyAuth,
+    State(state): State<Arc<AppState>>,
+) -> Json<GalaxyCacheStats> {
+    Json(state.galaxy_cache.snapshot())
 }
 
 pub async fn recent(
     State(state): State<Arc<AppState>>,
     Query(params): Query<GalaxyQuery>,
-) -> ApiResult<Json<Vec<GalaxyNode>>> {
+    headers: HeaderMap,
+) -> ApiResult<Response> {
     let limit = params.limit.unwrap_or(20).min(100);
-    let nodes = queries::get_recent_plays(state.graph.inner(), limit).await?;
-    Ok(Json(nodes))
+    let nodes = queries::get_recent_plays(
+        state.graph.inner(),
+        limit,
+        params.variant.unwrap_or_default(),
+    )
+    .await?;
+    conditional::negotiated_response(
+        &headers,
+        &nodes,
+        |n| PackedGalaxy::from_nodes(n),
+        REVALIDATE_CACHE_CONTROL,
+    )
 }
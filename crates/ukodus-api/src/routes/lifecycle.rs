@@ -0,0 +1,83 @@
+use std::sync::Arc;
+
+use axum::extract::{Path, Query, State};
+use axum::Json;
+
+use crate::error::{ApiError, ApiResult};
+use crate::extractors::{AdminAuth, ApiKeyAuth};
+use crate::graph::queries;
+use crate::models::lifecycle::{
+    LifecycleCount, LifecyclePuzzle, LifecycleQuery, PuzzleState, TransitionInput,
+    TransitionResponse,
+};
+use crate::services::galaxy_service;
+use crate::state::AppState;
+
+pub async fn counts(
+    _auth: ApiKeyAuth,
+    State(state): State<Arc<AppState>>,
+) -> ApiResult<Json<Vec<LifecycleCount>>> {
+    let counts = queries::get_lifecycle_counts(state.graph.inner()).await?;
+    Ok(Json(counts))
+}
+
+pub async fn list_by_state(
+    _auth: ApiKeyAuth,
+    State(state): State<Arc<AppState>>,
+    Path(puzzle_state): Path<String>,
+    Query(params): Query<LifecycleQuery>,
+) -> ApiResult<Json<Vec<LifecyclePuzzle>>> {
+    let puzzle_state = parse_state(&puzzle_state)?;
+    let limit = params.limit.unwrap_or(100).min(1000);
+    let puzzles = queries::list_puzzles_by_state(state.graph.inner(), puzzle_state, limit).await?;
+    Ok(Json(puzzles))
+}
+
+/// Manually move a puzzle between lifecycle states, e.g. to re-queue a
+/// failed analysis. Retiring goes through the retire endpoint so open
+/// reports are closed with it.
+pub async fn transition(
+    _auth: AdminAuth,
+    State(state): State<Arc<AppState>>,
+    Path(hash): Path<String>,
+    Json(input): Json<TransitionInput>,
+) -> ApiResult<Json<TransitionResponse>> {
+    let to = parse_state(&input.state)?;
+    if to == PuzzleState::Retired {
+        return Err(ApiError::BadRequest(format!(
+            "use POST /api/v1/admin/puzzles/{}/retire to retire a puzzle",
+            hash
+        )));
+    }
+
+    let from = queries::transition_puzzle(state.graph.inner(), &hash, PuzzleState::ALL, to).await?;
+    let Some(from) = from else {
+        let current = queries::get_puzzle_state(state.graph.inner(), &hash)
+            .await?
+            .ok_or_else(|| ApiError::NotFound(format!("puzzle {} not found", hash)))?;
+        return Err(ApiError::Conflict(format!(
+            "cannot move puzzle from {} to {}",
+            current,
+            to.as_str()
+        )));
+    };
+
+    if let Err(e) = galaxy_service::invalidate_cache_now(&state).await {
+        tracing::warn!("Galaxy cache invalidation failed: {e}");
+    }
+
+    Ok(Json(TransitionResponse {
+        puzzle_hash: hash,
+        from,
+        to,
+    }))
+}
+
+fn parse_state(s: &str) -> ApiResult<PuzzleState> {
+    PuzzleState::parse(s).ok_or_else(|| {
+        ApiError::BadRequest(format!(
+            "state must be one of: {}",
+            PuzzleState::names(PuzzleState::ALL).join(", ")
+        ))
+    })
+}
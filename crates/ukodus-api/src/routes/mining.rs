@@ -3,79 +3,67 @@ use std::sync::Arc;
 use axum::extract::{Query, State};
 use axum::Json;
 
-use crate::error::{ApiError, ApiResult};
+use crate::error::{ApiError, ApiResult, FieldError};
 use crate::extractors::ApiKeyAuth;
 use crate::graph::queries;
 use crate::models::puzzle::{
     MinedPuzzleInput, MinedPuzzleResponse, PoolInventoryResponse, PoolMonitoringResponse,
-    PuzzleDetail, UndiscoveredQuery,
+    PuzzleDetail, ShortCodeBackfillQuery, ShortCodeBackfillResponse, UndiscoveredQuery,
 };
 use crate::state::AppState;
+use ukodus_grid::{tier_index, Grid, DIFFICULTY_TIERS};
 
-const VALID_DIFFICULTIES: &[&str] = &["Hard", "Expert", "Master", "Extreme"];
+/// Mined puzzles are accepted from this tier up
+const MIN_MINED_TIER: &str = "Hard";
 
 pub async fn submit_mined(
     _auth: ApiKeyAuth,
     State(state): State<Arc<AppState>>,
     Json(input): Json<MinedPuzzleInput>,
 ) -> ApiResult<Json<MinedPuzzleResponse>> {
-    // Validate puzzle_string length
-    if input.puzzle_string.len() != 81 {
-        return Err(ApiError::BadRequest(
-            "puzzle_string must be 81 characters".into(),
-        ));
-    }
-
-    // Validate solution_string length
-    if input.solution_string.len() != 81 {
-        return Err(ApiError::BadRequest(
-            "solution_string must be 81 characters".into(),
-        ));
+    // Validate every field up front so the miner sees all problems at once
+    let mut errors = Vec::new();
+    let puzzle = Grid::parse(&input.puzzle_string)
+        .and_then(|grid| grid.check_rules(input.variant).map(|_| grid))
+        .map_err(|e| errors.push(FieldError::new("puzzle_string", e)))
+        .ok();
+    let solution = Grid::parse_solution(&input.solution_string)
+        .and_then(|grid| grid.check_rules(input.variant).map(|_| grid))
+        .map_err(|e| errors.push(FieldError::new("solution_string", e)))
+        .ok();
+    if let (Some(puzzle), Some(solution)) = (&puzzle, &solution) {
+        if let Err(e) = puzzle.check_solution(solution) {
+            errors.push(FieldError::new("solution_string", e));
+        }
     }
-
-    // Validate difficulty
-    if !VALID_DIFFICULTIES.contains(&input.difficulty.as_str()) {
-        return Err(ApiError::BadRequest(
-            format!("difficulty must be one of: {}", VALID_DIFFICULTIES.join(", ")),
+    let min_tier = tier_index(MIN_MINED_TIER).unwrap_or_default();
+    if tier_index(&input.difficulty).filter(|&i| i >= min_tier).is_none() {
+        let names: Vec<&str> = DIFFICULTY_TIERS[min_tier..].iter().map(|(name, ..)| *name).collect();
+        errors.push(FieldError::new(
+            "difficulty",
+            format!("must be one of: {}", names.join(", ")),
         ));
     }
+    let (puzzle, solution) = match (puzzle, solution) {
+        (Some(puzzle), Some(solution)) if errors.is_empty() => (puzzle, solution),
+        _ => return Err(ApiError::Validation(errors)),
+    };
 
-    // Validate puzzle_string characters (digits 1-9 and dots)
-    if !input
-        .puzzle_string
-        .chars()
-        .all(|c| c.is_ascii_digit() || c == '.')
-    {
-        return Err(ApiError::BadRequest(
-            "puzzle_string contains invalid characters".into(),
-        ));
-    }
+    let input = MinedPuzzleInput {
+        puzzle_string: puzzle.canonical(),
+        solution_string: solution.canonical(),
+        ..input
+    };
 
-    // Validate solution_string characters (digits 1-9 only)
-    if !input
-        .solution_string
-        .chars()
-        .all(|c| matches!(c, '1'..='9'))
-    {
-        return Err(ApiError::BadRequest(
-            "solution_string must contain only digits 1-9".into(),
-        ));
-    }
+    let (puzzle_hash, duplicate) = queries::upsert_mined_puzzle(state.graph.inner(), &input).await?;
 
-    let duplicate = queries::upsert_mined_puzzle(
-        state.graph.inner(),
-        &input.puzzle_hash,
-        &input.puzzle_string,
-        &input.solution_string,
-        &input.difficulty,
-        input.se_rating,
-        input.short_code.as_deref(),
-    )
-    .await?;
+    let short_code = queries::ensure_puzzle_short_code(state.graph.inner(), &puzzle_hash).await?;
 
     Ok(Json(MinedPuzzleResponse {
+        puzzle_hash,
         accepted: true,
         duplicate,
+        short_code,
     }))
 }
 
@@ -110,3 +98,15 @@ pub async fn pool_monitoring(
     let pools = queries::get_pool_monitoring(state.graph.inner()).await?;
     Ok(Json(PoolMonitoringResponse { pools }))
 }
+
+/// Allocate server short codes for puzzles and shares created before codes
+/// were server-assigned. Safe to call repeatedly until both counts are zero.
+pub async fn backfill_short_codes(
+    _auth: ApiKeyAuth,
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<ShortCodeBackfillQuery>,
+) -> ApiResult<Json<ShortCodeBackfillResponse>> {
+    let limit = params.limit.unwrap_or(500).min(5000);
+    let (puzzles, shares) = queries::backfill_short_codes(state.graph.inner(), limit).await?;
+    Ok(Json(ShortCodeBackfillResponse { puzzles, shares }))
+}
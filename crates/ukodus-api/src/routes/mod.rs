@@ -1,7 +1,9 @@
 pub mod galaxy;
 pub mod health;
+pub mod lifecycle;
 pub mod mining;
 pub mod puzzles;
+pub mod reports;
 pub mod results;
 pub mod share;
 pub mod techniques;
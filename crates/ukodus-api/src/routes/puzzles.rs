@@ -1,38 +1,47 @@
 use axum::extract::{Path, Query, State};
+use axum::http::HeaderMap;
+use axum::response::Response;
 use axum::Json;
 use serde::Deserialize;
 use std::sync::Arc;
+use ukodus_grid::Variant;
 
+use crate::conditional::{self, PUZZLE_CACHE_CONTROL};
 use crate::error::{ApiError, ApiResult};
 use crate::graph::queries;
-use crate::models::puzzle::PuzzleDetail;
+use crate::models::puzzle::{PuzzleDetail, PuzzleHeatmap, PuzzlePath, SolveTimeDistribution};
+use crate::services::{heatmap, move_log, solve_path};
 use crate::state::AppState;
 
 #[derive(Deserialize)]
 pub struct RandomQuery {
     pub difficulty: Option<String>,
+    /// Defaults to classic
+    pub variant: Option<Variant>,
 }
 
 pub async fn get_by_hash(
     State(state): State<Arc<AppState>>,
     Path(hash): Path<String>,
-) -> ApiResult<Json<PuzzleDetail>> {
+    headers: HeaderMap,
+) -> ApiResult<Response> {
     let puzzle = queries::get_puzzle_by_hash(state.graph.inner(), &hash)
         .await?
         .ok_or_else(|| ApiError::NotFound(format!("puzzle {} not found", hash)))?;
 
-    Ok(Json(puzzle))
+    conditional::json_response(&headers, &puzzle, PUZZLE_CACHE_CONTROL, puzzle.updated_at)
 }
 
 pub async fn get_by_code(
     State(state): State<Arc<AppState>>,
     Path(short_code): Path<String>,
-) -> ApiResult<Json<PuzzleDetail>> {
+    headers: HeaderMap,
+) -> ApiResult<Response> {
     let puzzle = queries::get_puzzle_by_code(state.graph.inner(), &short_code)
         .await?
         .ok_or_else(|| ApiError::NotFound(format!("puzzle with code {} not found", short_code)))?;
 
-    Ok(Json(puzzle))
+    conditional::json_response(&headers, &puzzle, PUZZLE_CACHE_CONTROL, puzzle.updated_at)
 }
 
 pub async fn get_random(
@@ -42,9 +51,10 @@ pub async fn get_random(
     let puzzle = queries::get_random_puzzle(
         state.graph.inner(),
         params.difficulty.as_deref(),
+        params.variant.unwrap_or_default(),
     )
     .await?
-    .ok_or_else(|| ApiError::NotFound("no analyzed puzzles available".to_string()))?;
+    .ok_or_else(|| ApiError::NotFound("no playable puzzles available".to_string()))?;
 
     Ok(Json(puzzle))
 }
@@ -59,3 +69,71 @@ pub async fn get_techniques(
 
     Ok(Json(puzzle.techniques))
 }
+
+pub async fn get_path(
+    State(state): State<Arc<AppState>>,
+    Path(hash): Path<String>,
+    headers: HeaderMap,
+) -> ApiResult<Response> {
+    let (encoded, max_se_rating, techniques) = queries::get_puzzle_path(state.graph.inner(), &hash)
+        .await?
+        .ok_or_else(|| ApiError::NotFound(format!("puzzle {} not found", hash)))?;
+    let encoded = encoded.ok_or_else(|| {
+        ApiError::NotFound(format!("puzzle {} has not been analyzed yet", hash))
+    })?;
+
+    let steps = solve_path::decode(&encoded, &techniques).map_err(ApiError::Internal)?;
+    let path = PuzzlePath {
+        puzzle_hash: hash,
+        max_se_rating,
+        steps,
+    };
+    conditional::json_response(&headers, &path, PUZZLE_CACHE_CONTROL, None)
+}
+
+/// Most recent games aggregated into a heatmap.
+const HEATMAP_MAX_GAMES: u64 = 500;
+
+pub async fn get_heatmap(
+    State(state): State<Arc<AppState>>,
+    Path(hash): Path<String>,
+    headers: HeaderMap,
+) -> ApiResult<Response> {
+    let (puzzle_string, variant, encoded_logs) =
+        queries::get_puzzle_move_logs(state.graph.inner(), &hash, HEATMAP_MAX_GAMES)
+            .await?
+            .ok_or_else(|| ApiError::NotFound(format!("puzzle {} not found", hash)))?;
+
+    // Skip logs that fail to decode rather than failing the whole heatmap
+    let logs: Vec<_> = encoded_logs
+        .iter()
+        .filter_map(|encoded| move_log::decode(encoded).ok())
+        .collect();
+
+    let path = match queries::get_puzzle_path(state.graph.inner(), &hash).await? {
+        Some((Some(encoded), _, techniques)) => {
+            solve_path::decode(&encoded, &techniques).unwrap_or_default()
+        }
+        _ => Vec::new(),
+    };
+
+    let cells = heatmap::aggregate(&puzzle_string, variant, &logs, &path)
+        .ok_or_else(|| ApiError::Internal(format!("puzzle {} has no solution", hash)))?;
+    let heatmap = PuzzleHeatmap {
+        puzzle_hash: hash,
+        games: logs.len() as u64,
+        cells,
+    };
+    conditional::json_response(&headers, &heatmap, PUZZLE_CACHE_CONTROL, None)
+}
+
+pub async fn get_solve_times(
+    State(state): State<Arc<AppState>>,
+    Path(hash): Path<String>,
+) -> ApiResult<Json<SolveTimeDistribution>> {
+    let distribution = queries::get_solve_time_distribution(state.graph.inner(), &hash)
+        .await?
+        .ok_or_else(|| ApiError::NotFound(format!("puzzle {} not found", hash)))?;
+
+    Ok(Json(distribution))
+}
@@ -0,0 +1,121 @@
+use std::sync::Arc;
+
+use axum::extract::{Path, Query, State};
+use axum::Json;
+
+use crate::error::{ApiError, ApiResult};
+use crate::extractors::AdminAuth;
+use crate::graph::queries;
+use crate::models::report::{
+    ReportDetail, ReportInput, ReportListQuery, ReportResponse, ResolveReportInput,
+    RetirePuzzleInput, RetirePuzzleResponse,
+};
+use crate::services::galaxy_service;
+use crate::state::AppState;
+
+const VALID_REASONS: &[&str] = &[
+    "wrong_givens",
+    "no_unique_solution",
+    "unsolvable",
+    "bad_share",
+    "wrong_difficulty",
+    "other",
+];
+const VALID_STATUSES: &[&str] = &["open", "resolved"];
+const MAX_COMMENT_LEN: usize = 500;
+
+pub async fn create_report(
+    State(state): State<Arc<AppState>>,
+    Path(hash): Path<String>,
+    Json(input): Json<ReportInput>,
+) -> ApiResult<Json<ReportResponse>> {
+    if !VALID_REASONS.contains(&input.reason.as_str()) {
+        return Err(ApiError::BadRequest(format!(
+            "reason must be one of: {}",
+            VALID_REASONS.join(", ")
+        )));
+    }
+    if input.player_id.is_empty() {
+        return Err(ApiError::BadRequest("player_id is required".into()));
+    }
+    let comment = input.comment.as_deref().map(str::trim).filter(|c| !c.is_empty());
+    if comment.is_some_and(|c| c.chars().count() > MAX_COMMENT_LEN) {
+        return Err(ApiError::BadRequest(format!(
+            "comment must be at most {} characters",
+            MAX_COMMENT_LEN
+        )));
+    }
+
+    let id = queries::create_report(
+        state.graph.inner(),
+        &hash,
+        &input.reason,
+        comment,
+        &input.player_id,
+    )
+    .await?
+    .ok_or_else(|| ApiError::NotFound(format!("puzzle {} not found", hash)))?;
+
+    Ok(Json(ReportResponse {
+        id,
+        status: "open".into(),
+    }))
+}
+
+pub async fn list_reports(
+    _auth: AdminAuth,
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<ReportListQuery>,
+) -> ApiResult<Json<Vec<ReportDetail>>> {
+    let status = params.status.as_deref().unwrap_or("open");
+    if !VALID_STATUSES.contains(&status) {
+        return Err(ApiError::BadRequest(format!(
+            "status must be one of: {}",
+            VALID_STATUSES.join(", ")
+        )));
+    }
+    let limit = params.limit.unwrap_or(50).min(200);
+    let offset = params.offset.unwrap_or(0);
+
+    let reports = queries::list_reports(state.graph.inner(), status, limit, offset).await?;
+    Ok(Json(reports))
+}
+
+pub async fn resolve_report(
+    _auth: AdminAuth,
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Json(input): Json<ResolveReportInput>,
+) -> ApiResult<Json<ReportDetail>> {
+    if input.resolution.trim().is_empty() {
+        return Err(ApiError::BadRequest("resolution is required".into()));
+    }
+
+    let report = queries::resolve_report(state.graph.inner(), &id, input.resolution.trim())
+        .await?
+        .ok_or_else(|| ApiError::NotFound(format!("report {} not found", id)))?;
+
+    Ok(Json(report))
+}
+
+pub async fn retire_puzzle(
+    _auth: AdminAuth,
+    State(state): State<Arc<AppState>>,
+    Path(hash): Path<String>,
+    Json(input): Json<RetirePuzzleInput>,
+) -> ApiResult<Json<RetirePuzzleResponse>> {
+    if input.reason.trim().is_empty() {
+        return Err(ApiError::BadRequest("reason is required".into()));
+    }
+
+    let resp = queries::retire_puzzle(state.graph.inner(), &hash, input.reason.trim())
+        .await?
+        .ok_or_else(|| ApiError::NotFound(format!("puzzle {} not found", hash)))?;
+
+    // Retired puzzles drop out of the galaxy overview
+    if let Err(e) = galaxy_service::invalidate_cache_now(&state).await {
+        tracing::warn!("Galaxy cache invalidation failed: {e}");
+    }
+
+    Ok(Json(resp))
+}
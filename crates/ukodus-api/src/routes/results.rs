@@ -1,40 +1,39 @@
-use axum::extract::{Query, State};
+use axum::extract::{Path, Query, State};
 use axum::Json;
 use std::sync::Arc;
 
 use crate::error::{ApiError, ApiResult};
 use crate::graph::queries;
+use crate::models::lifecycle::PuzzleState;
 use crate::models::puzzle::{
-    GameResultInput, GameResultResponse, LeaderboardEntry, LeaderboardQuery,
+    DiscovererEntry, DiscovererQuery, GameReplay, GameResultInput, GameResultResponse,
+    LeaderboardEntry, LeaderboardQuery,
 };
+use crate::services::move_log;
+use crate::services::result_service;
+use crate::services::solve_times;
 use crate::services::galaxy_service;
 use crate::services::result_service::AntiBot;
 use crate::state::AppState;
+use ukodus_grid::Grid;
+
+/// Longest accepted move log. A noted-out expert game stays well under a
+/// thousand moves; the cap keeps stored logs small.
+const MAX_MOVE_LOG_LEN: usize = 5_000;
 
 pub async fn submit_result(
     State(state): State<Arc<AppState>>,
     Json(input): Json<GameResultInput>,
 ) -> ApiResult<Json<GameResultResponse>> {
-    // Validate puzzle_string: 81 chars, digits 0-9 or '.' for empty cells
-    if input.puzzle_string.len() != 81 {
-        return Err(ApiError::BadRequest(
-            "puzzle_string must be exactly 81 characters".into(),
-        ));
-    }
-    if !input
-        .puzzle_string
-        .chars()
-        .all(|c| c.is_ascii_digit() || c == '.')
-    {
-        return Err(ApiError::BadRequest(
-            "puzzle_string must contain only digits 0-9 or '.'".into(),
-        ));
-    }
-    // Normalize dots to zeros for consistent storage
-    let input = {
-        let mut input = input;
-        input.puzzle_string = input.puzzle_string.replace('.', "0");
-        input
+    // Accept any grid notation; store the canonical form
+    let puzzle = Grid::parse(&input.puzzle_string)
+        .map_err(|e| ApiError::invalid("puzzle_string", e))?;
+    puzzle
+        .check_rules(input.variant)
+        .map_err(|e| ApiError::invalid("puzzle_string", e))?;
+    let input = GameResultInput {
+        puzzle_string: puzzle.canonical(),
+        ..input
     };
 
     // Validate result field
@@ -43,16 +42,24 @@ pub async fn submit_result(
             "result must be 'Win' or 'Loss'".into(),
         ));
     }
+    if input.move_log.as_ref().is_some_and(|log| log.len() > MAX_MOVE_LOG_LEN) {
+        return Err(ApiError::invalid(
+            "move_log",
+            format!("at most {} moves", MAX_MOVE_LOG_LEN),
+        ));
+    }
 
     // Anti-bot verification
     let verification = AntiBot::verify(&input);
 
     // Move log replay verification
     let mut replay_valid = true;
+    let mut note_activity = None;
     if let Some(ref log) = input.move_log {
         if !log.is_empty() {
             let replay = AntiBot::replay(
                 &input.puzzle_string,
+                input.variant,
                 log,
                 input.mistakes,
                 input.hints_used,
@@ -63,38 +70,64 @@ pub async fn submit_result(
                 }
                 replay_valid = false;
             }
+            note_activity = Some(replay.notes);
         }
     }
 
     let verified = verification.verified && replay_valid;
 
     // Upsert puzzle
-    let puzzle_is_new = queries::upsert_puzzle(
+    let (puzzle_hash, puzzle_is_new) = queries::upsert_puzzle(
         state.graph.inner(),
-        &input.puzzle_hash,
         &input.puzzle_string,
+        input.variant,
         input.short_code.as_deref(),
         &input.difficulty,
         input.se_rating,
     )
     .await?;
 
+    let short_code = queries::ensure_puzzle_short_code(state.graph.inner(), &puzzle_hash).await?;
+
     // Create game result
     let id = queries::create_game_result(
         state.graph.inner(),
-        &input.puzzle_hash,
+        &puzzle_hash,
         &input,
         verified,
+        note_activity.as_ref(),
     )
     .await?;
 
-    // Mark puzzle as discovered (transitions mined puzzles to public)
-    if let Err(e) = queries::mark_puzzle_discovered(state.graph.inner(), &input.puzzle_hash).await {
-        tracing::warn!("Failed to mark puzzle discovered: {e}");
+    // Mark puzzle as discovered (transitions mined puzzles to public). Only
+    // a verified win counts, so the discoverer board can't be farmed with
+    // losses or bot submissions.
+    if verified && input.result == "Win" {
+        match queries::mark_puzzle_discovered(state.graph.inner(), &puzzle_hash, &id).await {
+            Ok(Some(data)) => {
+                let msg = serde_json::json!({ "type": "puzzle_discovered", "data": data });
+                let _ = state.galaxy_tx.send(msg.to_string());
+            }
+            Ok(None) => {}
+            Err(e) => tracing::warn!("Failed to mark puzzle discovered: {e}"),
+        }
+    }
+
+    // Newly discovered classic puzzles (mined or player-submitted) go to the
+    // analyzer; variant puzzles stay discovered
+    if let Err(e) = queries::transition_puzzle(
+        state.graph.inner(),
+        &puzzle_hash,
+        &[PuzzleState::Discovered],
+        PuzzleState::Queued,
+    )
+    .await
+    {
+        tracing::warn!("Failed to queue puzzle for analysis: {e}");
     }
 
     // Update aggregates
-    queries::update_puzzle_aggregates(state.graph.inner(), &input.puzzle_hash).await?;
+    queries::update_puzzle_aggregates(state.graph.inner(), &puzzle_hash).await?;
 
     // Invalidate galaxy cache on new data
     if let Err(e) = galaxy_service::invalidate_cache(&state).await {
@@ -104,15 +137,30 @@ pub async fn submit_result(
     let leaderboard_eligible =
         verified && input.hints_used == 0 && input.mistakes < 3;
 
+    let faster_than_percent = if verified && input.result == "Win" {
+        let (slower, ties, others) = queries::get_solve_time_rank(
+            state.graph.inner(),
+            &puzzle_hash,
+            &id,
+            input.time_secs,
+        )
+        .await?;
+        solve_times::faster_than_percent(slower, ties, others)
+    } else {
+        None
+    };
+
     // Broadcast to WebSocket clients
     if puzzle_is_new {
         let msg = serde_json::json!({
             "type": "new_puzzle",
             "data": {
-                "puzzle_hash": &input.puzzle_hash,
-                "short_code": &input.short_code,
+                "puzzle_hash": &puzzle_hash,
+                "variant": input.variant.as_str(),
+                "short_code": &short_code,
                 "difficulty": &input.difficulty,
                 "se_rating": input.se_rating,
+                "rating_verified": false,
                 "play_count": 1u64,
                 "max_technique": Option::<String>::None,
                 "techniques": Vec::<String>::new(),
@@ -121,11 +169,11 @@ pub async fn submit_result(
         let _ = state.galaxy_tx.send(msg.to_string());
     } else {
         // Fetch updated play count for existing puzzle
-        if let Ok(count) = queries::get_puzzle_play_count(state.graph.inner(), &input.puzzle_hash).await {
+        if let Ok(count) = queries::get_puzzle_play_count(state.graph.inner(), &puzzle_hash).await {
             let msg = serde_json::json!({
                 "type": "play_result",
                 "data": {
-                    "puzzle_hash": &input.puzzle_hash,
+                    "puzzle_hash": &puzzle_hash,
                     "play_count": count,
                 }
             });
@@ -135,9 +183,12 @@ pub async fn submit_result(
 
     Ok(Json(GameResultResponse {
         id,
+        puzzle_hash,
+        short_code,
         verified,
         puzzle_is_new,
         leaderboard_eligible,
+        faster_than_percent,
     }))
 }
 
@@ -150,6 +201,7 @@ pub async fn leaderboard(
 
     let entries = queries::get_leaderboard(
         state.graph.inner(),
+        params.variant.unwrap_or_default(),
         params.difficulty.as_deref(),
         params.puzzle_hash.as_deref(),
         limit,
@@ -159,3 +211,29 @@ pub async fn leaderboard(
 
     Ok(Json(entries))
 }
+
+pub async fn replay(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> ApiResult<Json<GameReplay>> {
+    let (mut replay, encoded) = queries::get_game_replay(state.graph.inner(), &id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound(format!("result {} not found", id)))?;
+    let encoded = encoded
+        .ok_or_else(|| ApiError::NotFound(format!("result {} has no stored move log", id)))?;
+
+    let log = move_log::decode(&encoded).map_err(ApiError::Internal)?;
+    replay.moves = result_service::reconstruct(&replay.puzzle_string, replay.variant, &log)
+        .ok_or_else(|| ApiError::Internal(format!("puzzle {} has no solution", replay.puzzle_hash)))?;
+
+    Ok(Json(replay))
+}
+
+pub async fn top_discoverers(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<DiscovererQuery>,
+) -> ApiResult<Json<Vec<DiscovererEntry>>> {
+    let limit = params.limit.unwrap_or(50).min(100);
+    let entries = queries::get_top_discoverers(state.graph.inner(), limit).await?;
+    Ok(Json(entries))
+}
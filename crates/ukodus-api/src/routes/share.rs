@@ -7,30 +7,20 @@ use crate::error::{ApiError, ApiResult};
 use crate::graph::queries;
 use crate::models::galaxy::{GalaxyQuery, ShareDetail, ShareInput, ShareResponse};
 use crate::state::AppState;
+use ukodus_grid::Grid;
 
 pub async fn create_share(
     State(state): State<Arc<AppState>>,
     Json(input): Json<ShareInput>,
 ) -> ApiResult<Json<ShareResponse>> {
-    if input.puzzle_string.len() != 81 {
-        return Err(ApiError::BadRequest(
-            "puzzle_string must be exactly 81 characters".into(),
-        ));
-    }
-    if !input
-        .puzzle_string
-        .chars()
-        .all(|c| c.is_ascii_digit() || c == '.')
-    {
-        return Err(ApiError::BadRequest(
-            "puzzle_string must contain only digits 0-9 or '.'".into(),
-        ));
-    }
-    // Normalize dots to zeros
-    let input = {
-        let mut input = input;
-        input.puzzle_string = input.puzzle_string.replace('.', "0");
-        input
+    let puzzle = Grid::parse(&input.puzzle_string)
+        .map_err(|e| ApiError::invalid("puzzle_string", e))?;
+    puzzle
+        .check_rules(input.variant)
+        .map_err(|e| ApiError::invalid("puzzle_string", e))?;
+    let input = ShareInput {
+        puzzle_string: puzzle.canonical(),
+        ..input
     };
 
     let resp = queries::upsert_shared_puzzle(
@@ -78,19 +68,23 @@ pub async fn vanity_redirect(
     State(state): State<Arc<AppState>>,
     Path(id): Path<String>,
 ) -> Result<impl IntoResponse, ApiError> {
-    // Look up by share_id first, then try short_code
-    let detail = if let Some(d) = queries::get_share_by_id(state.graph.inner(), &id).await? {
-        d
-    } else if let Some(d) = queries::get_share_by_code(state.graph.inner(), &id).await? {
-        d
-    } else {
-        return Err(ApiError::NotFound(format!("share {} not found", id)));
-    };
+    // Look up by share_id first, then share short_code, then puzzle short_code
+    let (client_code, puzzle_string, variant) =
+        if let Some(d) = queries::get_share_by_id(state.graph.inner(), &id).await? {
+            (d.client_code, d.puzzle_string, d.variant)
+        } else if let Some(d) = queries::get_share_by_code(state.graph.inner(), &id).await? {
+            (d.client_code, d.puzzle_string, d.variant)
+        } else if let Some(p) = queries::get_puzzle_by_code(state.graph.inner(), &id).await? {
+            (None, p.puzzle_string, p.variant)
+        } else {
+            return Err(ApiError::NotFound(format!("share {} not found", id)));
+        };
 
-    let redirect_target = if let Some(ref code) = detail.short_code {
+    // The player decodes WASM codes locally; server codes are resolved here
+    let redirect_target = if let Some(code) = client_code {
         format!("/play/?s={}", code)
     } else {
-        format!("/play/?p={}", detail.puzzle_string)
+        format!("/play/?p={}&v={}", puzzle_string, variant.as_str())
     };
 
     Ok(Redirect::temporary(&redirect_target))
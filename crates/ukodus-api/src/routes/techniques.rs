@@ -1,11 +1,17 @@
 use axum::extract::{Path, Query, State};
+use axum::http::HeaderMap;
+use axum::response::Response;
 use axum::Json;
 use std::sync::Arc;
 
-use crate::error::ApiResult;
+use crate::conditional::{self, TECHNIQUES_CACHE_CONTROL};
+use crate::error::{ApiError, ApiResult};
 use crate::graph::queries;
 use crate::models::galaxy::GalaxyNode;
 use crate::models::puzzle::TechniqueInfo;
+use crate::models::technique::{
+    PrerequisiteGraph, TechniqueGraph, TechniqueGraphQuery, TechniquePrerequisites,
+};
 use crate::state::AppState;
 
 #[derive(serde::Deserialize)]
@@ -15,9 +21,45 @@ pub struct TechniqueQuery {
 
 pub async fn list_all(
     State(state): State<Arc<AppState>>,
-) -> ApiResult<Json<Vec<TechniqueInfo>>> {
-    let techniques = queries::get_all_techniques(state.graph.inner()).await?;
-    Ok(Json(techniques))
+    headers: HeaderMap,
+) -> ApiResult<Response> {
+    let techniques: Vec<TechniqueInfo> = queries::get_all_techniques(state.graph.inner()).await?;
+    conditional::json_response(&headers, &techniques, TECHNIQUES_CACHE_CONTROL, None)
+}
+
+pub async fn graph(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<TechniqueGraphQuery>,
+    headers: HeaderMap,
+) -> ApiResult<Response> {
+    let technique_graph: TechniqueGraph = queries::get_technique_graph(
+        state.graph.inner(),
+        params.min_count.unwrap_or(1),
+        params.min_lift.unwrap_or(0.0),
+    )
+    .await?;
+    conditional::json_response(&headers, &technique_graph, TECHNIQUES_CACHE_CONTROL, None)
+}
+
+pub async fn prerequisite_graph(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> ApiResult<Response> {
+    let dag: PrerequisiteGraph = queries::get_prerequisite_graph(state.graph.inner()).await?;
+    conditional::json_response(&headers, &dag, TECHNIQUES_CACHE_CONTROL, None)
+}
+
+/// What to learn before `name`, in learning order.
+pub async fn prerequisites(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+    headers: HeaderMap,
+) -> ApiResult<Response> {
+    let prerequisites: TechniquePrerequisites =
+        queries::get_technique_prerequisites(state.graph.inner(), &name)
+            .await?
+            .ok_or_else(|| ApiError::NotFound(format!("technique {} not found", name)))?;
+    conditional::json_response(&headers, &prerequisites, TECHNIQUES_CACHE_CONTROL, None)
 }
 
 pub async fn puzzles_by_technique(
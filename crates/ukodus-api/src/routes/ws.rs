@@ -9,7 +9,7 @@ use tokio_stream::StreamExt;
 use crate::state::AppState;
 
 /// SSE endpoint for live galaxy updates.
-/// Streams new_puzzle and play_result events as they happen.
+/// Streams new_puzzle, play_result and puzzle_discovered events as they happen.
 pub async fn galaxy_sse(
     State(state): State<Arc<AppState>>,
 ) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
@@ -0,0 +1,315 @@
+//! Stale-while-revalidate Redis cache for the expensive galaxy reads.
+//!
+//! Entries are stamped with the galaxy generation they were built from and
+//! a soft expiry. A fresh entry is returned as-is. A stale one (past its
+//! expiry, or built before the last invalidation) is still returned while
+//! one background task, holding a Redis lock, rebuilds it. Only a cold key
+//! makes a request wait: one request runs the query and the rest poll for
+//! its result.
+//!
+//! Invalidation bumps `galaxy:generation` instead of deleting keys, and the
+//! debounced form does so at most once per `INVALIDATE_DEBOUNCE_MS`. A change
+//! that lands inside the window is remembered and flushed by the first read
+//! after it closes.
+
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use redis::aio::ConnectionManager;
+use redis::AsyncCommands;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use tokio::time::Instant;
+use uuid::Uuid;
+
+use crate::error::ApiResult;
+use crate::models::galaxy::GalaxyCacheStats;
+use crate::state::AppState;
+
+const GENERATION_KEY: &str = "galaxy:generation";
+const DEBOUNCE_KEY: &str = "galaxy:invalidate:debounce";
+const PENDING_KEY: &str = "galaxy:invalidate:pending";
+const INVALIDATE_DEBOUNCE_MS: u64 = 5_000;
+/// How long an entry stays servable (stale) after its soft expiry
+const STALE_GRACE_SECS: u64 = 600;
+/// A refresh that takes longer than this loses its lock to the next one
+const LOCK_MS: u64 = 30_000;
+/// How long a cold request waits for another request's load before running
+/// (and storing) the query itself
+const COLD_WAIT: Duration = Duration::from_secs(10);
+const COLD_POLL: Duration = Duration::from_millis(100);
+/// Delete the lock only if it still holds our token, so a refresh that
+/// outlived `LOCK_MS` cannot release the next holder's lock
+const UNLOCK_SCRIPT: &str = r#"
+if redis.call("GET", KEYS[1]) == ARGV[1] then
+    return redis.call("DEL", KEYS[1])
+end
+return 0
+"#;
+
+#[derive(Serialize, Deserialize)]
+struct Entry<T> {
+    generation: u64,
+    fresh_until_ms: i64,
+    value: T,
+}
+
+fn is_fresh(entry_generation: u64, fresh_until_ms: i64, generation: u64, now_ms: i64) -> bool {
+    entry_generation >= generation && now_ms < fresh_until_ms
+}
+
+/// Per-process counters, served by `/internal/galaxy/cache`.
+#[derive(Debug, Default)]
+pub struct CacheCounters {
+    hits: AtomicU64,
+    stale_hits: AtomicU64,
+    misses: AtomicU64,
+    coalesced: AtomicU64,
+    refreshes: AtomicU64,
+    refresh_errors: AtomicU64,
+    invalidations: AtomicU64,
+    debounced_invalidations: AtomicU64,
+}
+
+impl CacheCounters {
+    fn bump(counter: &AtomicU64) {
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> GalaxyCacheStats {
+        let get = |c: &AtomicU64| c.load(Ordering::Relaxed);
+        GalaxyCacheStats {
+            hits: get(&self.hits),
+            stale_hits: get(&self.stale_hits),
+            misses: get(&self.misses),
+            coalesced: get(&self.coalesced),
+            refreshes: get(&self.refreshes),
+            refresh_errors: get(&self.refresh_errors),
+            invalidations: get(&self.invalidations),
+            debounced_invalidations: get(&self.debounced_invalidations),
+        }
+    }
+}
+
+/// Read `key` through the cache, running `load` on a miss or (in the
+/// background) when the entry is stale. Redis failures fall back to `load`.
+pub async fn cached<T, F, Fut>(
+    state: &AppState,
+    key: &str,
+    fresh_secs: u64,
+    load: F,
+) -> ApiResult<T>
+where
+    T: Serialize + DeserializeOwned + Send + 'static,
+    F: FnOnce(AppState) -> Fut + Send + 'static,
+    Fut: Future<Output = ApiResult<T>> + Send,
+{
+    let counters = &state.galaxy_cache;
+    let mut redis = state.redis.clone();
+    let lock_key = format!("{key}:lock");
+
+    let (raw, generation, pending, debouncing) = match redis
+        .mget::<_, (Option<String>, Option<u64>, Option<String>, Option<String>)>(&[
+            key,
+            GENERATION_KEY,
+            PENDING_KEY,
+            DEBOUNCE_KEY,
+        ])
+        .await
+    {
+        Ok(values) => values,
+        Err(e) => {
+            tracing::warn!("Galaxy cache read failed for {key}: {e}");
+            CacheCounters::bump(&counters.misses);
+            return load(state.clone()).await;
+        }
+    };
+    let mut generation = generation.unwrap_or(0);
+    if pending.is_some() && debouncing.is_none() {
+        if let Some(next) = flush_pending(state, &mut redis).await {
+            generation = next;
+        }
+    }
+
+    if let Some(entry) = raw.and_then(|r| serde_json::from_str::<Entry<T>>(&r).ok()) {
+        let now_ms = chrono::Utc::now().timestamp_millis();
+        if is_fresh(entry.generation, entry.fresh_until_ms, generation, now_ms) {
+            CacheCounters::bump(&counters.hits);
+            return Ok(entry.value);
+        }
+        CacheCounters::bump(&counters.stale_hits);
+        if let Some(token) = try_lock(&mut redis, &lock_key).await {
+            let state = state.clone();
+            let key = key.to_string();
+            tokio::spawn(async move {
+                let lock = Some((lock_key.as_str(), token.as_str()));
+                let _ = refresh(&state, &key, lock, generation, fresh_secs, load).await;
+            });
+        }
+        return Ok(entry.value);
+    }
+
+    CacheCounters::bump(&counters.misses);
+    if let Some(token) = try_lock(&mut redis, &lock_key).await {
+        let lock = Some((lock_key.as_str(), token.as_str()));
+        return refresh(state, key, lock, generation, fresh_secs, load).await;
+    }
+
+    // Someone else is already loading this key: wait for their result
+    let deadline = Instant::now() + COLD_WAIT;
+    while Instant::now() < deadline {
+        tokio::time::sleep(COLD_POLL).await;
+        if let Ok(Some(raw)) = redis.get::<_, Option<String>>(key).await {
+            if let Ok(entry) = serde_json::from_str::<Entry<T>>(&raw) {
+                CacheCounters::bump(&counters.coalesced);
+                return Ok(entry.value);
+            }
+        }
+    }
+    // They are slow or gone: load and store it ourselves, taking the lock
+    // if it has expired in the meantime
+    let token = try_lock(&mut redis, &lock_key).await;
+    let lock = token.as_deref().map(|t| (lock_key.as_str(), t));
+    refresh(state, key, lock, generation, fresh_secs, load).await
+}
+
+/// Run `load`, store the result stamped with `generation` and release
+/// `lock` (key and token) if held. The lock is released on failure too, so
+/// the next request retries.
+async fn refresh<T, F, Fut>(
+    state: &AppState,
+    key: &str,
+    lock: Option<(&str, &str)>,
+    generation: u64,
+    fresh_secs: u64,
+    load: F,
+) -> ApiResult<T>
+where
+    T: Serialize,
+    F: FnOnce(AppState) -> Fut,
+    Fut: Future<Output = ApiResult<T>>,
+{
+    let counters = &state.galaxy_cache;
+    let mut redis = state.redis.clone();
+    let result = load(state.clone()).await;
+    // Serialize before awaiting so the future does not hold `&result`
+    let json = match &result {
+        Ok(value) => {
+            CacheCounters::bump(&counters.refreshes);
+            serde_json::to_string(&Entry {
+                generation,
+                fresh_until_ms: chrono::Utc::now().timestamp_millis() + fresh_secs as i64 * 1000,
+                value,
+            })
+            .ok()
+        }
+        Err(e) => {
+            CacheCounters::bump(&counters.refresh_errors);
+            tracing::warn!("Galaxy cache refresh failed for {key}: {e}");
+            None
+        }
+    };
+    if let Some(json) = json {
+        let _: Result<(), _> = redis
+            .set_ex::<_, _, ()>(key, json, fresh_secs + STALE_GRACE_SECS)
+            .await;
+    }
+    if let Some((lock_key, token)) = lock {
+        let _: Result<i64, _> = redis::Script::new(UNLOCK_SCRIPT)
+            .key(lock_key)
+            .arg(token)
+            .invoke_async(&mut redis)
+            .await;
+    }
+    result
+}
+
+/// `SET NX` lock holding a random token, returned when acquired. A Redis
+/// error counts as acquired so callers fall through to loading instead of
+/// waiting on a lock nobody holds.
+async fn try_lock(redis: &mut ConnectionManager, lock_key: &str) -> Option<String> {
+    let token = Uuid::new_v4().to_string();
+    let acquired = redis::cmd("SET")
+        .arg(lock_key)
+        .arg(&token)
+        .arg("NX")
+        .arg("PX")
+        .arg(LOCK_MS)
+        .query_async::<Option<String>>(redis)
+        .await
+        .map_or(true, |acquired| acquired.is_some());
+    acquired.then_some(token)
+}
+
+/// Mark every galaxy entry stale. With `debounce`, at most one generation
+/// bump happens per interval; later calls inside it are deferred.
+pub async fn invalidate(state: &AppState, debounce: bool) -> ApiResult<()> {
+    let counters = &state.galaxy_cache;
+    let mut redis = state.redis.clone();
+    if debounce {
+        let acquired: Option<String> = redis::cmd("SET")
+            .arg(DEBOUNCE_KEY)
+            .arg(1)
+            .arg("NX")
+            .arg("PX")
+            .arg(INVALIDATE_DEBOUNCE_MS)
+            .query_async(&mut redis)
+            .await?;
+        if acquired.is_none() {
+            CacheCounters::bump(&counters.debounced_invalidations);
+            redis.set::<_, _, ()>(PENDING_KEY, 1).await?;
+            return Ok(());
+        }
+    }
+    redis.del::<_, ()>(PENDING_KEY).await?;
+    redis.incr::<_, _, ()>(GENERATION_KEY, 1).await?;
+    CacheCounters::bump(&counters.invalidations);
+    Ok(())
+}
+
+/// Apply an invalidation deferred by the debounce window, if this request
+/// wins the next window. Returns the new generation.
+async fn flush_pending(state: &AppState, redis: &mut ConnectionManager) -> Option<u64> {
+    let acquired: Option<String> = redis::cmd("SET")
+        .arg(DEBOUNCE_KEY)
+        .arg(1)
+        .arg("NX")
+        .arg("PX")
+        .arg(INVALIDATE_DEBOUNCE_MS)
+        .query_async(redis)
+        .await
+        .ok()?;
+    acquired?;
+    let _: Result<(), _> = redis.del::<_, ()>(PENDING_KEY).await;
+    let generation = redis.incr::<_, _, u64>(GENERATION_KEY, 1).await.ok()?;
+    CacheCounters::bump(&state.galaxy_cache.invalidations);
+    Some(generation)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_fresh() {
+        // Within its soft expiry and built from the current generation
+        assert!(is_fresh(3, 2_000, 3, 1_000));
+        // Expired
+        assert!(!is_fresh(3, 2_000, 3, 2_000));
+        // Built before the last invalidation
+        assert!(!is_fresh(2, 2_000, 3, 1_000));
+        // Built after a bump this reader has not seen yet
+        assert!(is_fresh(4, 2_000, 3, 1_000));
+    }
+
+    #[test]
+    fn test_counters_snapshot() {
+        let counters = CacheCounters::default();
+        CacheCounters::bump(&counters.hits);
+        CacheCounters::bump(&counters.hits);
+        CacheCounters::bump(&counters.stale_hits);
+        let stats = counters.snapshot();
+        assert_eq!((stats.hits, stats.stale_hits, stats.misses), (2, 1, 0));
+    }
+}
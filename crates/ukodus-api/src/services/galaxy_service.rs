@@ -1,101 +1,156 @@
-use redis::AsyncCommands;
+use ukodus_grid::Variant;
 
 use crate::error::{ApiError, ApiResult};
 use crate::graph::queries;
-use crate::models::galaxy::{GalaxyOverview, GalaxyStats};
+use crate::models::galaxy::{
+    GalaxyOverview, GalaxyStats, GalaxyTimeline, OverviewParams, TimelineBucket,
+};
+use crate::services::{galaxy_cache, timeline};
 use crate::state::AppState;
 
 const GALAXY_OVERVIEW_KEY: &str = "galaxy:overview";
 const GALAXY_STATS_KEY: &str = "galaxy:stats";
+const GALAXY_TIMELINE_KEY: &str = "galaxy:timeline";
+/// How long entries stay fresh; stale ones are still served during a rebuild
 const OVERVIEW_TTL_SECS: u64 = 60;
 const STATS_TTL_SECS: u64 = 30;
+const TIMELINE_TTL_SECS: u64 = 300;
+
+/// One cache entry per distinct overview request. Filters are
+/// validated by the route and percent-encoded where they could contain the
+/// `:` separator; `as_of` arrives rounded to the minute and is keyed by its
+/// epoch milliseconds.
+fn overview_cache_key(params: &OverviewParams) -> String {
+    format!(
+        "{}:{}:{}:{}:{}:d={}:t={}:a={}",
+        GALAXY_OVERVIEW_KEY,
+        params.variant.as_str(),
+        params.limit,
+        params.states.join(","),
+        params.sort.as_str(),
+        params
+            .difficulty
+            .as_deref()
+            .map(escape_key_part)
+            .unwrap_or_default(),
+        params
+            .technique
+            .as_deref()
+            .map(escape_key_part)
+            .unwrap_or_default(),
+        params
+            .as_of
+            .map(|t| t.timestamp_millis().to_string())
+            .unwrap_or_default(),
+    )
+}
+
+fn escape_key_part(s: &str) -> String {
+    s.replace('%', "%25").replace(':', "%3A")
+}
 
 pub async fn get_cached_overview(
     state: &AppState,
-    limit: u64,
+    params: &OverviewParams,
 ) -> ApiResult<GalaxyOverview> {
-    let cache_key = format!("{}:{}", GALAXY_OVERVIEW_KEY, limit);
-
-    // Try cache first
-    let mut redis = state.redis.clone();
-    if let Ok(cached) = redis.get::<_, Option<String>>(&cache_key).await {
-        if let Some(json) = cached {
-            if let Ok(overview) = serde_json::from_str::<GalaxyOverview>(&json) {
-                return Ok(overview);
-            }
-        }
-    }
-
-    // Cache miss: query graph
-    let overview = queries::get_galaxy_overview(state.graph.inner(), limit).await?;
+    let params = params.clone();
+    galaxy_cache::cached(
+        state,
+        &overview_cache_key(&params),
+        OVERVIEW_TTL_SECS,
+        move |state| async move { queries::get_galaxy_overview(state.graph.inner(), &params).await },
+    )
+    .await
+}
 
-    // Store in cache
-    if let Ok(json) = serde_json::to_string(&overview) {
-        let _: Result<(), _> = redis
-            .set_ex::<_, _, ()>(&cache_key, json, OVERVIEW_TTL_SECS)
-            .await;
-    }
+pub async fn get_cached_stats(state: &AppState, variant: Variant) -> ApiResult<GalaxyStats> {
+    let cache_key = format!("{}:{}", GALAXY_STATS_KEY, variant.as_str());
+    galaxy_cache::cached(state, &cache_key, STATS_TTL_SECS, move |state| async move {
+        queries::get_galaxy_stats(state.graph.inner(), variant).await
+    })
+    .await
+}
 
-    Ok(overview)
+pub async fn get_cached_timeline(
+    state: &AppState,
+    bucket: TimelineBucket,
+    states: &[String],
+    variant: Variant,
+) -> ApiResult<GalaxyTimeline> {
+    let cache_key = format!(
+        "{}:{}:{}:{}",
+        GALAXY_TIMELINE_KEY,
+        variant.as_str(),
+        bucket.as_str(),
+        states.join(",")
+    );
+    let states = states.to_vec();
+    galaxy_cache::cached(
+        state,
+        &cache_key,
+        TIMELINE_TTL_SECS,
+        move |state| async move {
+            let (nodes, edges) =
+                queries::get_timeline_counts(state.graph.inner(), bucket, &states, variant).await?;
+            Ok(GalaxyTimeline {
+                bucket,
+                buckets: timeline::merge_buckets(&nodes, &edges),
+            })
+        },
+    )
+    .await
 }
 
-pub async fn get_cached_stats(state: &AppState) -> ApiResult<GalaxyStats> {
-    let mut redis = state.redis.clone();
+/// Mark cached galaxy reads stale after new results, debounced so a burst of
+/// submissions triggers one rebuild. Readers keep getting the stale entries
+/// until it finishes.
+pub async fn invalidate_cache(state: &AppState) -> Result<(), ApiError> {
+    galaxy_cache::invalidate(state, true).await
+}
 
-    if let Ok(cached) = redis.get::<_, Option<String>>(GALAXY_STATS_KEY).await {
-        if let Some(json) = cached {
-            if let Ok(stats) = serde_json::from_str::<GalaxyStats>(&json) {
-                return Ok(stats);
-            }
-        }
-    }
+/// Like [`invalidate_cache`] without the debounce, for moderation actions
+/// that must drop a puzzle from the galaxy straight away.
+pub async fn invalidate_cache_now(state: &AppState) -> Result<(), ApiError> {
+    galaxy_cache::invalidate(state, false).await
+}
 
-    let stats = queries::get_galaxy_stats(state.graph.inner()).await?;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::galaxy::OverviewSort;
 
-    if let Ok(json) = serde_json::to_string(&stats) {
-        let _: Result<(), _> = redis
-            .set_ex::<_, _, ()>(GALAXY_STATS_KEY, json, STATS_TTL_SECS)
-            .await;
-    }
+    #[test]
+    fn test_overview_cache_key_covers_params() {
+        let base = OverviewParams {
+            limit: 500,
+            states: vec!["discovered".into(), "analyzed".into()],
+            variant: Variant::Classic,
+            sort: OverviewSort::Plays,
+            difficulty: None,
+            technique: None,
+            as_of: None,
+        };
+        assert_eq!(
+            overview_cache_key(&base),
+            "galaxy:overview:classic:500:discovered,analyzed:plays:d=:t=:a="
+        );
 
-    Ok(stats)
-}
+        let sampled = OverviewParams {
+            sort: OverviewSort::Sample,
+            difficulty: Some("Hard".into()),
+            technique: Some("X-Wing:odd".into()),
+            ..base.clone()
+        };
+        assert_eq!(
+            overview_cache_key(&sampled),
+            "galaxy:overview:classic:500:discovered,analyzed:sample:d=Hard:t=X-Wing%3Aodd:a="
+        );
+        assert_ne!(overview_cache_key(&base), overview_cache_key(&sampled));
 
-pub async fn invalidate_cache(state: &AppState) -> Result<(), ApiError> {
-    let mut redis = state.redis.clone();
-    // Delete stats cache
-    if let Err(e) = redis.del::<_, ()>(GALAXY_STATS_KEY).await {
-        tracing::warn!("Failed to invalidate galaxy stats cache: {e}");
-    }
-    // Delete overview keys by scanning for the pattern
-    // (DEL does not support glob patterns — must use SCAN + DEL)
-    let pattern = format!("{}:*", GALAXY_OVERVIEW_KEY);
-    let mut cursor: u64 = 0;
-    loop {
-        let (next_cursor, keys): (u64, Vec<String>) = match redis::cmd("SCAN")
-            .arg(cursor)
-            .arg("MATCH")
-            .arg(&pattern)
-            .arg("COUNT")
-            .arg(100)
-            .query_async(&mut redis)
-            .await
-        {
-            Ok(result) => result,
-            Err(e) => {
-                tracing::warn!("Failed to scan galaxy overview cache keys: {e}");
-                break;
-            }
+        let past = OverviewParams {
+            as_of: chrono::DateTime::from_timestamp_millis(1_700_000_000_000),
+            ..base.clone()
         };
-        if !keys.is_empty() {
-            if let Err(e) = redis.del::<_, ()>(&keys).await {
-                tracing::warn!("Failed to delete {} galaxy overview cache keys: {e}", keys.len());
-            }
-        }
-        if next_cursor == 0 {
-            break;
-        }
-        cursor = next_cursor;
+        assert!(overview_cache_key(&past).ends_with(":a=1700000000000"));
     }
-    Ok(())
 }
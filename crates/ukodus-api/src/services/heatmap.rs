@@ -0,0 +1,121 @@
+//! Per-cell aggregation of stored move logs for one puzzle.
+
+use ukodus_grid::Variant;
+
+use crate::models::puzzle::{CellHeat, MoveAction, MoveLogEntry, PathStep, StepAction};
+use crate::services::result_service::puzzle_and_solution;
+
+/// Count wrong placements, hints and time-to-fill for every cell across `logs`.
+///
+/// Time-to-fill is the game clock at the first correct placement or hint in
+/// the cell, so cells filled early in typical games score low. `path` is the
+/// analyzer's solving path, used to tag each cell with the step and
+/// technique that logically places it. Returns `None` if the puzzle is
+/// malformed or has no solution.
+pub fn aggregate(
+    puzzle_string: &str,
+    variant: Variant,
+    logs: &[Vec<MoveLogEntry>],
+    path: &[PathStep],
+) -> Option<Vec<CellHeat>> {
+    let (puzzle, solution) = puzzle_and_solution(puzzle_string, variant)?;
+
+    let mut cells: Vec<CellHeat> = (0..81u8)
+        .map(|cell| CellHeat {
+            cell,
+            row: cell / 9,
+            col: cell % 9,
+            given: puzzle[cell as usize] != 0,
+            mistakes: 0,
+            hints: 0,
+            fills: 0,
+            median_time_to_fill_ms: None,
+            path_step: None,
+            technique: None,
+        })
+        .collect();
+
+    let mut fill_times: Vec<Vec<u32>> = vec![Vec::new(); 81];
+    for log in logs {
+        let mut filled = [false; 81];
+        for entry in log.iter().filter(|e| e.cell < 81) {
+            let idx = entry.cell as usize;
+            let correct_fill = match entry.action {
+                MoveAction::Place(v) if v == solution[idx] => true,
+                MoveAction::Place(v) if (1..=9).contains(&v) => {
+                    cells[idx].mistakes += 1;
+                    false
+                }
+                MoveAction::Hint(_) => {
+                    cells[idx].hints += 1;
+                    true
+                }
+                _ => false,
+            };
+            if correct_fill && !filled[idx] {
+                filled[idx] = true;
+                cells[idx].fills += 1;
+                fill_times[idx].push(entry.ms);
+            }
+        }
+    }
+
+    for (cell, mut times) in cells.iter_mut().zip(fill_times) {
+        if !times.is_empty() {
+            times.sort_unstable();
+            cell.median_time_to_fill_ms = Some(times[times.len() / 2]);
+        }
+    }
+
+    for step in path.iter().filter(|s| s.action == StepAction::Place) {
+        let cell = &mut cells[(step.row * 9 + step.col) as usize];
+        if cell.path_step.is_none() {
+            cell.path_step = Some(step.index);
+            cell.technique = Some(step.technique.clone());
+        }
+    }
+
+    Some(cells)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::result_service::TEST_PUZZLE;
+
+    #[test]
+    fn test_aggregate_counts_per_cell() {
+        let (puzzle, solution) = puzzle_and_solution(TEST_PUZZLE, Variant::Classic).unwrap();
+        let idx = (0..81).find(|&i| puzzle[i] == 0).unwrap();
+        let wrong = if solution[idx] == 9 { 1 } else { solution[idx] + 1 };
+        let mv = |seq, ms, action| MoveLogEntry { seq, ms, cell: idx as u8, action };
+
+        let logs = vec![
+            vec![
+                mv(0, 1000, MoveAction::Place(wrong)),
+                mv(1, 4000, MoveAction::Place(solution[idx])),
+            ],
+            vec![mv(0, 9000, MoveAction::Hint(solution[idx]))],
+            vec![mv(0, 2000, MoveAction::Place(solution[idx]))],
+        ];
+        let path = vec![PathStep {
+            index: 3,
+            technique: "HiddenSingle".into(),
+            display_name: "Hidden Single".into(),
+            se_rating: 1.2,
+            row: (idx / 9) as u8,
+            col: (idx % 9) as u8,
+            action: StepAction::Place,
+            values: vec![solution[idx]],
+        }];
+
+        let cells = aggregate(TEST_PUZZLE, Variant::Classic, &logs, &path).unwrap();
+        assert_eq!(cells.len(), 81);
+        assert!(cells[0].given);
+        let cell = &cells[idx];
+        assert_eq!((cell.mistakes, cell.hints, cell.fills), (1, 1, 3));
+        assert_eq!(cell.median_time_to_fill_ms, Some(4000));
+        assert_eq!(cell.path_step, Some(3));
+        assert_eq!(cell.technique.as_deref(), Some("HiddenSingle"));
+    }
+}
@@ -0,0 +1,109 @@
+//! Learning-path search across the similarity graph.
+//!
+//! A path only climbs: every hop goes to a puzzle rated at least as high as
+//! the current one. Each hop costs the square of the number of techniques the
+//! next puzzle needs that the current one did not, plus a small per-hop
+//! cost. Squaring makes one big jump dearer than several small ones, so the
+//! cheapest route introduces techniques one at a time where the graph
+//! allows, and otherwise prefers fewer stepping stones.
+
+use std::cmp::Reverse;
+use std::collections::{BTreeSet, BinaryHeap};
+
+/// Cost of one hop, relative to a hop introducing one new technique.
+const HOP_COST: u64 = 1;
+const TECHNIQUE_COST: u64 = 1000;
+
+pub struct Candidate {
+    pub se_rating: f64,
+    pub techniques: BTreeSet<String>,
+}
+
+/// Techniques `to` needs that `from` does not.
+pub fn new_techniques(from: &Candidate, to: &Candidate) -> Vec<String> {
+    to.techniques.difference(&from.techniques).cloned().collect()
+}
+
+/// Cheapest climbing path from `start` to `goal` over undirected `edges`,
+/// as candidate indices including both ends.
+pub fn find_path(
+    candidates: &[Candidate],
+    edges: &[(usize, usize)],
+    start: usize,
+    goal: usize,
+) -> Option<Vec<usize>> {
+    let n = candidates.len();
+    let mut adjacency: Vec<Vec<usize>> = vec![Vec::new(); n];
+    for &(a, b) in edges {
+        adjacency[a].push(b);
+        adjacency[b].push(a);
+    }
+
+    let mut cost = vec![u64::MAX; n];
+    let mut previous: Vec<Option<usize>> = vec![None; n];
+    let mut heap = BinaryHeap::new();
+    cost[start] = 0;
+    heap.push(Reverse((0u64, start)));
+
+    while let Some(Reverse((c, u))) = heap.pop() {
+        if u == goal {
+            break;
+        }
+        if c > cost[u] {
+            continue;
+        }
+        for &v in &adjacency[u] {
+            if candidates[v].se_rating < candidates[u].se_rating {
+                continue;
+            }
+            let added = new_techniques(&candidates[u], &candidates[v]).len() as u64;
+            let step = HOP_COST + TECHNIQUE_COST * added * added;
+            let next = c + step;
+            if next < cost[v] {
+                cost[v] = next;
+                previous[v] = Some(u);
+                heap.push(Reverse((next, v)));
+            }
+        }
+    }
+
+    if cost[goal] == u64::MAX {
+        return None;
+    }
+    let mut path = vec![goal];
+    while let Some(p) = previous[*path.last().unwrap()] {
+        path.push(p);
+    }
+    path.reverse();
+    Some(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidate(se_rating: f64, techniques: &[&str]) -> Candidate {
+        Candidate {
+            se_rating,
+            techniques: techniques.iter().map(|t| t.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_path_adds_techniques_gradually() {
+        let candidates = vec![
+            candidate(1.5, &["Single"]),
+            // Direct jump: two new techniques at once
+            candidate(4.2, &["Single", "Pair", "X-Wing"]),
+            candidate(3.0, &["Single", "Pair"]),
+            candidate(4.2, &["Single", "Pair", "X-Wing"]),
+            // Easier than the start: never on a climbing path
+            candidate(1.2, &["Single"]),
+        ];
+        let edges = vec![(0, 1), (0, 2), (2, 3), (3, 1), (0, 4), (4, 1)];
+        assert_eq!(find_path(&candidates, &edges, 0, 1), Some(vec![0, 2, 3, 1]));
+        assert_eq!(find_path(&candidates, &edges, 0, 3), Some(vec![0, 2, 3]));
+        assert_eq!(find_path(&candidates, &edges, 3, 0), None);
+        assert_eq!(find_path(&candidates, &edges, 2, 2), Some(vec![2]));
+    }
+}
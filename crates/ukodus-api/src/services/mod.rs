@@ -1,2 +1,11 @@
+pub mod galaxy_cache;
 pub mod galaxy_service;
+pub mod heatmap;
+pub mod learning_path;
+pub mod move_log;
 pub mod result_service;
+pub mod short_code;
+pub mod solve_path;
+pub mod solve_times;
+pub mod timeline;
+pub mod viewport;
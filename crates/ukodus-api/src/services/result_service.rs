@@ -1,4 +1,6 @@
-use crate::models::puzzle::{GameResultInput, MoveAction, MoveLogEntry};
+use ukodus_grid::{Grid, Variant};
+
+use crate::models::puzzle::{GameResultInput, MoveAction, MoveLogEntry, ReplayMove};
 
 pub struct AntiBot;
 
@@ -12,6 +14,22 @@ pub struct ReplayResult {
     pub issues: Vec<String>,
     pub server_mistakes: u32,
     pub server_hints: u32,
+    pub notes: NoteActivity,
+}
+
+/// Pencil-mark activity seen during replay.
+///
+/// Humans on hard puzzles note candidates before committing to a digit;
+/// scripted solvers almost never do.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct NoteActivity {
+    pub notes_added: u32,
+    pub notes_removed: u32,
+    pub auto_notes: u32,
+    /// Placements into a cell that had pencil marks at the time
+    pub placements_on_noted_cells: u32,
+    /// Share of all placements that went into a noted cell
+    pub noted_placement_ratio: f32,
 }
 
 impl AntiBot {
@@ -94,27 +112,38 @@ impl AntiBot {
     }
 
     /// Replay a move log against the puzzle to validate the game was actually played.
-    pub fn replay(puzzle_string: &str, log: &[MoveLogEntry], client_mistakes: u32, client_hints: u32) -> ReplayResult {
+    ///
+    /// `variant` decides which cells see each other, for the solver and for
+    /// auto-notes.
+    pub fn replay(
+        puzzle_string: &str,
+        variant: Variant,
+        log: &[MoveLogEntry],
+        client_mistakes: u32,
+        client_hints: u32,
+    ) -> ReplayResult {
         let mut issues = Vec::new();
 
-        // Parse puzzle into [u8; 81]
-        let mut puzzle = [0u8; 81];
-        for (i, ch) in puzzle_string.chars().enumerate() {
-            if i >= 81 { break; }
-            puzzle[i] = ch.to_digit(10).unwrap_or(0) as u8;
-        }
-
-        // Solve to get the solution
-        let solution = match solve_backtrack(&puzzle) {
-            Some(s) => s,
+        let (puzzle, solution) = match puzzle_and_solution(puzzle_string, variant) {
+            Some(parsed) => parsed,
             None => {
-                issues.push("puzzle has no solution".to_string());
-                return ReplayResult { valid: false, issues, server_mistakes: 0, server_hints: 0 };
+                issues.push("puzzle is malformed or has no solution".to_string());
+                return ReplayResult {
+                    valid: false,
+                    issues,
+                    server_mistakes: 0,
+                    server_hints: 0,
+                    notes: NoteActivity::default(),
+                };
             }
         };
 
         // Replay moves
         let mut board = puzzle;
+        // Pencil marks per cell as a bitmask (bit d set = candidate d noted)
+        let mut candidates = [0u16; 81];
+        let mut notes = NoteActivity::default();
+        let mut placements: u32 = 0;
         let mut server_mistakes: u32 = 0;
         let mut server_hints: u32 = 0;
         let mut prev_seq: Option<u32> = None;
@@ -150,7 +179,12 @@ impl AntiBot {
             match &entry.action {
                 MoveAction::Place(v) => {
                     if *v >= 1 && *v <= 9 {
+                        placements += 1;
+                        if candidates[idx] != 0 {
+                            notes.placements_on_noted_cells += 1;
+                        }
                         board[idx] = *v;
+                        clear_notes_for_placement(&mut candidates, idx, *v, variant);
                         if *v != solution[idx] {
                             server_mistakes += 1;
                         }
@@ -169,12 +203,42 @@ impl AntiBot {
                 MoveAction::Hint(v) => {
                     if *v >= 1 && *v <= 9 {
                         board[idx] = *v;
+                        clear_notes_for_placement(&mut candidates, idx, *v, variant);
                         server_hints += 1;
                     }
                 }
                 MoveAction::Undo(val) | MoveAction::Redo(val) => {
+                    // Undo/redo only carry the cell value, so pencil marks
+                    // touched by an undone note action are left as they are
                     board[idx] = val.unwrap_or(0);
                 }
+                MoveAction::NoteAdd(v) | MoveAction::NoteRemove(v) => {
+                    if !(1..=9).contains(v) {
+                        issues.push(format!("invalid note digit {} at cell {}", v, idx));
+                    } else if board[idx] != 0 {
+                        // A note on a filled cell changes nothing, so a stray
+                        // tap there is not treated as a bot signal
+                        tracing::debug!("ignoring note on filled cell {}", idx);
+                    } else if matches!(entry.action, MoveAction::NoteAdd(_)) {
+                        candidates[idx] |= 1 << v;
+                        notes.notes_added += 1;
+                    } else {
+                        candidates[idx] &= !(1 << v);
+                        notes.notes_removed += 1;
+                    }
+                }
+                MoveAction::AutoNotes => {
+                    for (i, cell) in candidates.iter_mut().enumerate() {
+                        *cell = if board[i] == 0 {
+                            (1..=9u8)
+                                .filter(|d| variant.allows(&board, i, *d))
+                                .fold(0, |mask, d| mask | (1 << d))
+                        } else {
+                            0
+                        };
+                    }
+                    notes.auto_notes += 1;
+                }
             }
         }
 
@@ -206,34 +270,115 @@ impl AntiBot {
             }
         }
 
+        if placements > 0 {
+            notes.noted_placement_ratio = notes.placements_on_noted_cells as f32 / placements as f32;
+        }
+
         ReplayResult {
             valid: issues.is_empty(),
             issues,
             server_mistakes,
             server_hints,
+            notes,
+        }
+    }
+}
+
+/// Rebuild the board after every move of a stored log.
+///
+/// Returns `None` if the puzzle is malformed or has no solution. Moves on
+/// invalid cells are kept, with the board left unchanged, so reviewers see
+/// the log as sent.
+pub fn reconstruct(
+    puzzle_string: &str,
+    variant: Variant,
+    log: &[MoveLogEntry],
+) -> Option<Vec<ReplayMove>> {
+    let (mut board, solution) = puzzle_and_solution(puzzle_string, variant)?;
+
+    let moves = log
+        .iter()
+        .map(|entry| {
+            let idx = entry.cell as usize;
+            let (action, value) = match entry.action {
+                MoveAction::Place(v) => ("place", Some(v)),
+                MoveAction::Clear(v) => ("clear", Some(v)),
+                MoveAction::Hint(v) => ("hint", Some(v)),
+                MoveAction::Undo(v) => ("undo", v),
+                MoveAction::Redo(v) => ("redo", v),
+                MoveAction::NoteAdd(v) => ("note_add", Some(v)),
+                MoveAction::NoteRemove(v) => ("note_remove", Some(v)),
+                MoveAction::AutoNotes => ("auto_notes", None),
+            };
+            let mut correct = None;
+            if idx < 81 {
+                match entry.action {
+                    MoveAction::Place(v) if (1..=9).contains(&v) => {
+                        board[idx] = v;
+                        correct = Some(v == solution[idx]);
+                    }
+                    MoveAction::Hint(v) if (1..=9).contains(&v) => board[idx] = v,
+                    MoveAction::Clear(_) => board[idx] = 0,
+                    MoveAction::Undo(v) | MoveAction::Redo(v) => board[idx] = v.unwrap_or(0),
+                    _ => {}
+                }
+            }
+            ReplayMove {
+                seq: entry.seq,
+                ms: entry.ms,
+                cell: entry.cell,
+                action,
+                value,
+                correct,
+                board: board.iter().map(|d| (b'0' + d) as char).collect(),
+            }
+        })
+        .collect();
+    Some(moves)
+}
+
+/// Placing a digit clears the cell's own marks and that digit from its peers.
+fn clear_notes_for_placement(candidates: &mut [u16; 81], idx: usize, digit: u8, variant: Variant) {
+    candidates[idx] = 0;
+    for (i, cell) in candidates.iter_mut().enumerate() {
+        if variant.sees(idx, i) {
+            *cell &= !(1 << digit);
         }
     }
 }
 
+/// Parse a stored puzzle and solve it. `None` if the grid is malformed or
+/// has no solution.
+pub fn puzzle_and_solution(puzzle_string: &str, variant: Variant) -> Option<([u8; 81], [u8; 81])> {
+    let puzzle = *Grid::parse(puzzle_string).ok()?.cells();
+    let solution = solve_backtrack(&puzzle, variant)?;
+    Some((puzzle, solution))
+}
+
+/// Shared by the tests of every service that replays move logs
+#[cfg(test)]
+pub const TEST_PUZZLE: &str =
+    "530070000600195000098000060800060003400803001700020006060000280000419005000080079";
+
 /// Minimal backtracking solver with MRV heuristic.
 /// Returns the unique solution if one exists.
-fn solve_backtrack(puzzle: &[u8; 81]) -> Option<[u8; 81]> {
+pub fn solve_backtrack(puzzle: &[u8; 81], variant: Variant) -> Option<[u8; 81]> {
     let mut board = *puzzle;
-    if solve_recursive(&mut board) {
+    if solve_recursive(&mut board, variant) {
         Some(board)
     } else {
         None
     }
 }
 
-fn solve_recursive(board: &mut [u8; 81]) -> bool {
+fn solve_recursive(board: &mut [u8; 81], variant: Variant) -> bool {
     // Find empty cell with fewest candidates (MRV)
     let mut best_idx = None;
     let mut best_count = 10u32;
 
     for i in 0..81 {
         if board[i] == 0 {
-            let count = count_candidates(board, i);
+            let count = count_candidates(board, i, variant);
             if count == 0 {
                 return false; // dead end
             }
@@ -251,9 +396,9 @@ fn solve_recursive(board: &mut [u8; 81]) -> bool {
     };
 
     for digit in 1..=9u8 {
-        if is_valid(board, idx, digit) {
+        if variant.allows(board, idx, digit) {
             board[idx] = digit;
-            if solve_recursive(board) {
+            if solve_recursive(board, variant) {
                 return true;
             }
             board[idx] = 0;
@@ -263,69 +408,31 @@ fn solve_recursive(board: &mut [u8; 81]) -> bool {
     false
 }
 
-fn count_candidates(board: &[u8; 81], idx: usize) -> u32 {
+fn count_candidates(board: &[u8; 81], idx: usize, variant: Variant) -> u32 {
     let mut count = 0;
     for d in 1..=9u8 {
-        if is_valid(board, idx, d) {
+        if variant.allows(board, idx, d) {
             count += 1;
         }
     }
     count
 }
 
-fn is_valid(board: &[u8; 81], idx: usize, digit: u8) -> bool {
-    let row = idx / 9;
-    let col = idx % 9;
-
-    // Check row
-    for c in 0..9 {
-        if board[row * 9 + c] == digit {
-            return false;
-        }
-    }
-
-    // Check column
-    for r in 0..9 {
-        if board[r * 9 + col] == digit {
-            return false;
-        }
-    }
-
-    // Check 3x3 box
-    let box_r = (row / 3) * 3;
-    let box_c = (col / 3) * 3;
-    for r in box_r..box_r + 3 {
-        for c in box_c..box_c + 3 {
-            if board[r * 9 + c] == digit {
-                return false;
-            }
-        }
-    }
-
-    true
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::models::puzzle::MoveLogEntry;
 
-    const TEST_PUZZLE: &str = "530070000600195000098000060800060003400803001700020006060000280000419005000080079";
-
     #[test]
     fn test_solver_finds_solution() {
-        let mut puzzle = [0u8; 81];
-        for (i, ch) in TEST_PUZZLE.chars().enumerate() {
-            puzzle[i] = ch.to_digit(10).unwrap_or(0) as u8;
-        }
-        let solution = solve_backtrack(&puzzle).expect("should solve");
+        let (puzzle, solution) =
+            puzzle_and_solution(TEST_PUZZLE, Variant::Classic).expect("should solve");
         // Every cell should be 1-9
         for &v in &solution {
             assert!(v >= 1 && v <= 9, "cell has value {}", v);
         }
         // Givens should be preserved
-        for (i, ch) in TEST_PUZZLE.chars().enumerate() {
-            let given = ch.to_digit(10).unwrap_or(0) as u8;
+        for (i, &given) in puzzle.iter().enumerate() {
             if given != 0 {
                 assert_eq!(solution[i], given, "given at {} mismatch", i);
             }
@@ -334,11 +441,7 @@ mod tests {
 
     #[test]
     fn test_replay_clean_game() {
-        let mut puzzle = [0u8; 81];
-        for (i, ch) in TEST_PUZZLE.chars().enumerate() {
-            puzzle[i] = ch.to_digit(10).unwrap_or(0) as u8;
-        }
-        let solution = solve_backtrack(&puzzle).unwrap();
+        let (puzzle, solution) = puzzle_and_solution(TEST_PUZZLE, Variant::Classic).unwrap();
 
         // Build a move log that places all empty cells correctly
         let mut log = Vec::new();
@@ -357,7 +460,7 @@ mod tests {
             }
         }
 
-        let result = AntiBot::replay(TEST_PUZZLE, &log, 0, 0);
+        let result = AntiBot::replay(TEST_PUZZLE, Variant::Classic, &log, 0, 0);
         assert!(result.valid, "clean game should be valid: {:?}", result.issues);
         assert_eq!(result.server_mistakes, 0);
         assert_eq!(result.server_hints, 0);
@@ -365,11 +468,7 @@ mod tests {
 
     #[test]
     fn test_replay_detects_mistake_mismatch() {
-        let mut puzzle = [0u8; 81];
-        for (i, ch) in TEST_PUZZLE.chars().enumerate() {
-            puzzle[i] = ch.to_digit(10).unwrap_or(0) as u8;
-        }
-        let solution = solve_backtrack(&puzzle).unwrap();
+        let (puzzle, solution) = puzzle_and_solution(TEST_PUZZLE, Variant::Classic).unwrap();
 
         // Find first empty cell and place wrong value
         let empty_idx = (0..81).find(|&i| puzzle[i] == 0).unwrap();
@@ -380,18 +479,14 @@ mod tests {
         ];
 
         // Client claims 0 mistakes, but server sees 1
-        let result = AntiBot::replay(TEST_PUZZLE, &log, 0, 0);
+        let result = AntiBot::replay(TEST_PUZZLE, Variant::Classic, &log, 0, 0);
         assert!(!result.valid, "should flag mistake mismatch");
         assert_eq!(result.server_mistakes, 1);
     }
 
     #[test]
     fn test_replay_detects_hint_counting() {
-        let mut puzzle = [0u8; 81];
-        for (i, ch) in TEST_PUZZLE.chars().enumerate() {
-            puzzle[i] = ch.to_digit(10).unwrap_or(0) as u8;
-        }
-        let solution = solve_backtrack(&puzzle).unwrap();
+        let (puzzle, solution) = puzzle_and_solution(TEST_PUZZLE, Variant::Classic).unwrap();
         let empty_idx = (0..81).find(|&i| puzzle[i] == 0).unwrap();
 
         let log = vec![
@@ -400,7 +495,7 @@ mod tests {
         ];
 
         // Client claims 0 hints, server sees 2
-        let result = AntiBot::replay(TEST_PUZZLE, &log, 0, 0);
+        let result = AntiBot::replay(TEST_PUZZLE, Variant::Classic, &log, 0, 0);
         assert!(!result.valid, "should flag hint mismatch");
         assert_eq!(result.server_hints, 2);
     }
@@ -411,7 +506,7 @@ mod tests {
             MoveLogEntry { seq: 0, ms: 1000, cell: 0, action: MoveAction::Place(1) },
             MoveLogEntry { seq: 5, ms: 2000, cell: 1, action: MoveAction::Place(2) }, // gap
         ];
-        let result = AntiBot::replay(TEST_PUZZLE, &log, 2, 0);
+        let result = AntiBot::replay(TEST_PUZZLE, Variant::Classic, &log, 2, 0);
         assert!(!result.valid, "should flag seq gap");
         assert!(result.issues.iter().any(|i| i.contains("seq gap")));
     }
@@ -423,11 +518,86 @@ mod tests {
             MoveLogEntry { seq: 1, ms: 1010, cell: 1, action: MoveAction::Place(2) }, // 10ms delta
             MoveLogEntry { seq: 2, ms: 1020, cell: 2, action: MoveAction::Place(3) }, // 10ms delta
         ];
-        let result = AntiBot::replay(TEST_PUZZLE, &log, 3, 0);
+        let result = AntiBot::replay(TEST_PUZZLE, Variant::Classic, &log, 3, 0);
         assert!(!result.valid, "should flag fast timing");
         assert!(result.issues.iter().any(|i| i.contains("min move delta")));
     }
 
+    #[test]
+    fn test_replay_tracks_notes() {
+        let (puzzle, solution) = puzzle_and_solution(TEST_PUZZLE, Variant::Classic).unwrap();
+        let empty: Vec<usize> = (0..81).filter(|&i| puzzle[i] == 0).collect();
+        let (a, b) = (empty[0], empty[1]);
+
+        let log = vec![
+            MoveLogEntry { seq: 0, ms: 1000, cell: a as u8, action: MoveAction::NoteAdd(solution[a]) },
+            MoveLogEntry { seq: 1, ms: 1600, cell: a as u8, action: MoveAction::NoteAdd(9) },
+            MoveLogEntry { seq: 2, ms: 2400, cell: a as u8, action: MoveAction::NoteRemove(9) },
+            MoveLogEntry { seq: 3, ms: 3000, cell: a as u8, action: MoveAction::Place(solution[a]) },
+            MoveLogEntry { seq: 4, ms: 3700, cell: 0, action: MoveAction::AutoNotes },
+            MoveLogEntry { seq: 5, ms: 4500, cell: b as u8, action: MoveAction::Place(solution[b]) },
+            // The cell is filled now, so the note is ignored
+            MoveLogEntry { seq: 6, ms: 5200, cell: b as u8, action: MoveAction::NoteAdd(1) },
+        ];
+
+        let result = AntiBot::replay(TEST_PUZZLE, Variant::Classic, &log, 0, 0);
+        assert_eq!(result.notes.notes_added, 2);
+        assert_eq!(result.notes.notes_removed, 1);
+        assert_eq!(result.notes.auto_notes, 1);
+        assert_eq!(result.notes.placements_on_noted_cells, 2);
+        assert!((result.notes.noted_placement_ratio - 1.0).abs() < f32::EPSILON);
+        assert!(result.issues.is_empty(), "{:?}", result.issues);
+        assert!(result.valid);
+    }
+
+    #[test]
+    fn test_clear_notes_for_placement() {
+        let mut candidates = [0u16; 81];
+        candidates[1] = 0b11_1111_1110;
+        candidates[9] = 0b11_1111_1110;
+        candidates[80] = 0b11_1111_1110;
+        clear_notes_for_placement(&mut candidates, 0, 5, Variant::Classic);
+        assert_eq!(candidates[0], 0);
+        assert_eq!(candidates[1] & (1 << 5), 0, "same row");
+        assert_eq!(candidates[9] & (1 << 5), 0, "same column and box");
+        assert_ne!(candidates[80] & (1 << 5), 0, "unrelated cell");
+    }
+
+    #[test]
+    fn test_reconstruct_flags_placements() {
+        let (puzzle, solution) = puzzle_and_solution(TEST_PUZZLE, Variant::Classic).unwrap();
+        let idx = (0..81).find(|&i| puzzle[i] == 0).unwrap();
+        let wrong = if solution[idx] == 9 { 1 } else { solution[idx] + 1 };
+
+        let log = vec![
+            MoveLogEntry { seq: 0, ms: 1000, cell: idx as u8, action: MoveAction::Place(wrong) },
+            MoveLogEntry { seq: 1, ms: 1800, cell: idx as u8, action: MoveAction::Clear(wrong) },
+            MoveLogEntry { seq: 2, ms: 2600, cell: idx as u8, action: MoveAction::Place(solution[idx]) },
+        ];
+        let moves = reconstruct(TEST_PUZZLE, Variant::Classic, &log).unwrap();
+        assert_eq!(moves.len(), 3);
+        assert_eq!(moves[0].correct, Some(false));
+        assert_eq!(moves[1].correct, None);
+        assert_eq!(moves[1].board, TEST_PUZZLE);
+        assert_eq!(moves[2].correct, Some(true));
+        assert_eq!(moves[2].board.as_bytes()[idx], b'0' + solution[idx]);
+    }
+
+    #[test]
+    fn test_diagonal_solver_and_notes() {
+        let solution = solve_backtrack(&[0u8; 81], Variant::Diagonal).expect("should solve");
+        let solved: String = solution.iter().map(|d| (b'0' + d) as char).collect();
+        let grid = ukodus_grid::Grid::parse(&solved).unwrap();
+        assert_eq!(grid.check_rules(Variant::Diagonal), Ok(()));
+
+        // r1c1 and r9c9 only share the main diagonal
+        let mut candidates = [0b11_1111_1110u16; 81];
+        clear_notes_for_placement(&mut candidates, 0, 5, Variant::Classic);
+        assert_ne!(candidates[80] & (1 << 5), 0);
+        clear_notes_for_placement(&mut candidates, 0, 5, Variant::Diagonal);
+        assert_eq!(candidates[80] & (1 << 5), 0);
+    }
+
     #[test]
     fn test_replay_partial_log_ok() {
         // Partial log (from save/load) — server sees fewer mistakes than client
@@ -435,7 +605,7 @@ mod tests {
             MoveLogEntry { seq: 0, ms: 1000, cell: 0, action: MoveAction::Place(5) },
         ];
         // Client claims 2 mistakes — server may see 0 or 1, but that's OK (only flag if higher)
-        let result = AntiBot::replay(TEST_PUZZLE, &log, 2, 0);
+        let result = AntiBot::replay(TEST_PUZZLE, Variant::Classic, &log, 2, 0);
         // Should not flag mistake mismatch since server <= client
         assert!(!result.issues.iter().any(|i| i.contains("mistake count mismatch")));
     }
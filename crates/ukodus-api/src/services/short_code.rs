@@ -0,0 +1,78 @@
+//! Server-allocated short codes for puzzles and shares.
+//!
+//! Codes are drawn from an alphabet without look-alike characters
+//! (no 0/O, 1/I/L, or U) so they survive being read aloud or retyped from a
+//! screenshot. A code is derived deterministically from a seed (puzzle hash
+//! or share id) plus an attempt counter, so re-running allocation for an
+//! existing node always proposes the same sequence of candidates. Uniqueness
+//! itself is enforced by Neo4j constraints; callers move on to the next
+//! attempt when a candidate is already taken.
+
+/// 30 unambiguous characters.
+pub const ALPHABET: &[u8] = b"23456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+/// Server codes are 6 characters (~729M combinations). Client-generated
+/// WASM codes are 8, so the two can never be confused.
+pub const CODE_LEN: usize = 6;
+
+/// Candidates tried before giving up on a seed.
+pub const MAX_ATTEMPTS: u32 = 16;
+
+/// The `attempt`-th candidate code for `seed`.
+pub fn candidate(seed: &str, attempt: u32) -> String {
+    // FNV-1a over the seed and attempt counter
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in seed.bytes().chain(attempt.to_le_bytes()) {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+
+    let base = ALPHABET.len() as u64;
+    (0..CODE_LEN)
+        .map(|_| {
+            let c = ALPHABET[(hash % base) as usize] as char;
+            hash /= base;
+            c
+        })
+        .collect()
+}
+
+/// Canonical form of a user-supplied server code, or `None` if it cannot be one.
+pub fn normalize(code: &str) -> Option<String> {
+    let code = code.trim().to_ascii_uppercase();
+    if code.len() == CODE_LEN && code.bytes().all(|b| ALPHABET.contains(&b)) {
+        Some(code)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_candidate_is_deterministic() {
+        assert_eq!(candidate("abc", 0), candidate("abc", 0));
+        assert_ne!(candidate("abc", 0), candidate("abc", 1));
+        assert_ne!(candidate("abc", 0), candidate("abd", 0));
+    }
+
+    #[test]
+    fn test_candidate_uses_unambiguous_alphabet() {
+        for attempt in 0..100 {
+            let code = candidate("some-puzzle-hash", attempt);
+            assert_eq!(code.len(), CODE_LEN);
+            assert!(!code.contains(['0', 'O', '1', 'I', 'L', 'U']), "{code}");
+        }
+    }
+
+    #[test]
+    fn test_normalize() {
+        let code = candidate("seed", 0);
+        assert_eq!(normalize(&code.to_lowercase()), Some(code.clone()));
+        assert_eq!(normalize(&format!(" {code} ")), Some(code));
+        assert_eq!(normalize("ABC"), None);
+        assert_eq!(normalize("ABCDE0"), None);
+    }
+}
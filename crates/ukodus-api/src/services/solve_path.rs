@@ -0,0 +1,94 @@
+//! Decoder for the analyzer's compact `p.solve_path` encoding.
+//!
+//! Steps are `;`-separated `<Technique>:<cell><op><digits>` entries, where
+//! `cell` is the row-major index, `op` is `=` (place) or `-` (eliminate) and
+//! `digits` are the placed value or eliminated candidates. See the analyzer's
+//! `path` module for the writer.
+
+use std::collections::HashMap;
+
+use crate::models::puzzle::{PathStep, StepAction};
+
+/// Technique enum name -> (display name, SE rating)
+pub type TechniqueLookup = HashMap<String, (String, f32)>;
+
+/// Decode a stored path, filling in display names and ratings from `techniques`.
+///
+/// Unknown techniques keep their enum name as display name and a rating of 0.
+pub fn decode(encoded: &str, techniques: &TechniqueLookup) -> Result<Vec<PathStep>, String> {
+    if encoded.is_empty() {
+        return Ok(Vec::new());
+    }
+    encoded
+        .split(';')
+        .enumerate()
+        .map(|(index, entry)| {
+            decode_step(index, entry, techniques).ok_or_else(|| format!("bad step {index}: {entry:?}"))
+        })
+        .collect()
+}
+
+fn decode_step(index: usize, entry: &str, techniques: &TechniqueLookup) -> Option<PathStep> {
+    let (technique, rest) = entry.split_once(':')?;
+    let op_at = rest.find(['=', '-'])?;
+    let cell: u8 = rest[..op_at].parse().ok().filter(|c| *c < 81)?;
+    let values = rest[op_at + 1..]
+        .chars()
+        .map(|c| c.to_digit(10).filter(|d| (1..=9).contains(d)).map(|d| d as u8))
+        .collect::<Option<Vec<u8>>>()?;
+
+    let action = match &rest[op_at..op_at + 1] {
+        "=" if values.len() == 1 => StepAction::Place,
+        "-" if !values.is_empty() => StepAction::Eliminate,
+        _ => return None,
+    };
+    let (display_name, se_rating) = techniques
+        .get(technique)
+        .cloned()
+        .unwrap_or_else(|| (technique.to_string(), 0.0));
+
+    Some(PathStep {
+        index: index as u32,
+        technique: technique.to_string(),
+        display_name,
+        se_rating,
+        row: cell / 9,
+        col: cell % 9,
+        action,
+        values,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lookup() -> TechniqueLookup {
+        let mut t = HashMap::new();
+        t.insert("HiddenSingle".to_string(), ("Hidden Single".to_string(), 1.2));
+        t.insert("XWing".to_string(), ("X-Wing".to_string(), 3.2));
+        t
+    }
+
+    #[test]
+    fn test_decode_path() {
+        let steps = decode("HiddenSingle:40=5;XWing:12-37", &lookup()).unwrap();
+        assert_eq!(steps.len(), 2);
+        assert_eq!((steps[0].row, steps[0].col), (4, 4));
+        assert_eq!(steps[0].action, StepAction::Place);
+        assert_eq!(steps[0].values, vec![5]);
+        assert_eq!(steps[1].display_name, "X-Wing");
+        assert_eq!((steps[1].row, steps[1].col), (1, 3));
+        assert_eq!(steps[1].action, StepAction::Eliminate);
+        assert_eq!(steps[1].values, vec![3, 7]);
+        assert!(decode("", &lookup()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_decode_rejects_malformed() {
+        assert!(decode("HiddenSingle:81=5", &lookup()).is_err());
+        assert!(decode("HiddenSingle:4=55", &lookup()).is_err());
+        assert!(decode("HiddenSingle:4-0", &lookup()).is_err());
+        assert!(decode("HiddenSingle40=5", &lookup()).is_err());
+    }
+}
@@ -0,0 +1,57 @@
+//! Solve-time histogram buckets and percentile-rank helpers.
+
+use crate::models::puzzle::HistogramBucket;
+
+/// Upper bounds (exclusive, seconds) of the histogram buckets. A final
+/// open-ended bucket holds everything at or above the last edge, so there
+/// are `BUCKET_EDGES_SECS.len() + 1` buckets. Roughly log-spaced so both
+/// Beginner and Extreme puzzles get a useful shape.
+pub const BUCKET_EDGES_SECS: &[i64] = &[
+    30, 60, 90, 120, 180, 240, 300, 420, 600, 900, 1200, 1800, 2700, 3600,
+];
+
+/// Pair stored bucket counts with their bounds. Missing counts read as 0.
+pub fn buckets(counts: &[i64]) -> Vec<HistogramBucket> {
+    (0..=BUCKET_EDGES_SECS.len())
+        .map(|i| HistogramBucket {
+            min_secs: if i == 0 { 0 } else { BUCKET_EDGES_SECS[i - 1] as u64 },
+            max_secs: BUCKET_EDGES_SECS.get(i).map(|e| *e as u64),
+            count: counts.get(i).copied().unwrap_or(0).max(0) as u64,
+        })
+        .collect()
+}
+
+/// Percentage of *other* verified winners that were slower, counting ties
+/// as half. `slower` and `ties` exclude the player's own result; `others`
+/// is the number of other verified wins. `None` when there is nobody to
+/// compare against.
+pub fn faster_than_percent(slower: u64, ties: u64, others: u64) -> Option<f64> {
+    if others == 0 {
+        return None;
+    }
+    let rank = (slower as f64 + ties as f64 / 2.0) / others as f64;
+    Some((rank * 1000.0).round() / 10.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_buckets_cover_all_times() {
+        let b = buckets(&[1, 2]);
+        assert_eq!(b.len(), BUCKET_EDGES_SECS.len() + 1);
+        assert_eq!((b[0].min_secs, b[0].max_secs, b[0].count), (0, Some(30), 1));
+        assert_eq!((b[1].min_secs, b[1].max_secs, b[1].count), (30, Some(60), 2));
+        let last = b.last().unwrap();
+        assert_eq!((last.min_secs, last.max_secs, last.count), (3600, None, 0));
+    }
+
+    #[test]
+    fn test_faster_than_percent() {
+        assert_eq!(faster_than_percent(0, 0, 0), None);
+        assert_eq!(faster_than_percent(3, 0, 4), Some(75.0));
+        assert_eq!(faster_than_percent(1, 1, 3), Some(50.0));
+        assert_eq!(faster_than_percent(0, 0, 7), Some(0.0));
+    }
+}
@@ -0,0 +1,102 @@
+//! Growth history for `/galaxy/timeline` and `?as_of=` snapshots.
+
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, SecondsFormat, Utc};
+
+use crate::error::ApiError;
+use crate::models::galaxy::TimelineEntry;
+
+/// Parse an `as_of` query value (RFC 3339, any offset).
+pub fn parse_as_of(value: &str) -> Result<DateTime<Utc>, ApiError> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|t| t.with_timezone(&Utc))
+        .map_err(|_| ApiError::invalid("as_of", "must be an RFC 3339 timestamp"))
+}
+
+/// Round `as_of` down to the minute, so nearby timestamps share one
+/// overview cache entry instead of each creating its own.
+pub fn quantize_as_of(t: DateTime<Utc>) -> DateTime<Utc> {
+    DateTime::from_timestamp(t.timestamp().div_euclid(60) * 60, 0).unwrap_or(t)
+}
+
+/// Join per-bucket node and edge counts, keyed by bucket start in epoch
+/// milliseconds, into one series with running totals.
+pub fn merge_buckets(nodes: &[(i64, u64)], edges: &[(i64, u64)]) -> Vec<TimelineEntry> {
+    let mut buckets: BTreeMap<i64, (u64, u64)> = BTreeMap::new();
+    for &(start, count) in nodes {
+        buckets.entry(start).or_default().0 += count;
+    }
+    for &(start, count) in edges {
+        buckets.entry(start).or_default().1 += count;
+    }
+
+    let (mut total_nodes, mut total_edges) = (0, 0);
+    buckets
+        .into_iter()
+        .map(|(start, (nodes, edges))| {
+            total_nodes += nodes;
+            total_edges += edges;
+            TimelineEntry {
+                start: DateTime::from_timestamp_millis(start)
+                    .unwrap_or_default()
+                    .to_rfc3339_opts(SecondsFormat::Secs, true),
+                nodes,
+                edges,
+                total_nodes,
+                total_edges,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: i64 = 86_400_000;
+
+    #[test]
+    fn test_quantize_as_of() {
+        let t = parse_as_of("2026-03-01T12:34:56.789+02:00").unwrap();
+        assert_eq!(
+            quantize_as_of(t).to_rfc3339_opts(SecondsFormat::Millis, true),
+            "2026-03-01T10:34:00.000Z"
+        );
+    }
+
+    #[test]
+    fn test_merge_buckets_running_totals() {
+        let nodes = [(DAY * 2, 5), (0, 3)];
+        let edges = [(DAY, 4), (DAY * 2, 6)];
+        let merged = merge_buckets(&nodes, &edges);
+        let summary: Vec<(&str, u64, u64, u64, u64)> = merged
+            .iter()
+            .map(|e| {
+                (
+                    e.start.as_str(),
+                    e.nodes,
+                    e.edges,
+                    e.total_nodes,
+                    e.total_edges,
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("1970-01-01T00:00:00Z", 3, 0, 3, 0),
+                ("1970-01-02T00:00:00Z", 0, 4, 3, 4),
+                ("1970-01-03T00:00:00Z", 5, 6, 8, 10),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_as_of() {
+        let t = parse_as_of("2026-03-01T12:00:00+02:00").unwrap();
+        assert_eq!(t.to_rfc3339(), "2026-03-01T10:00:00+00:00");
+        assert!(parse_as_of("yesterday").is_err());
+        assert!(parse_as_of("2026-03-01").is_err());
+    }
+}
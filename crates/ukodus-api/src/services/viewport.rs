@@ -0,0 +1,161 @@
+//! Level-of-detail helpers for the galaxy viewport.
+
+use std::collections::BTreeMap;
+
+use crate::error::FieldError;
+use crate::models::galaxy::{GalaxyCentroid, ViewportQuery};
+
+/// At or above this zoom the viewport returns individual puzzles.
+pub const DETAIL_ZOOM: f64 = 1.0;
+/// On-screen size, in pixels, of one aggregation cell below `DETAIL_ZOOM`.
+const CELL_PIXELS: f64 = 64.0;
+
+/// Puzzles of one difficulty in one aggregation cell, as counted by Neo4j.
+pub struct CellRow {
+    pub cx: i64,
+    pub cy: i64,
+    pub difficulty: String,
+    pub count: u64,
+    pub play_count: u64,
+    pub sum_x: f64,
+    pub sum_y: f64,
+}
+
+pub fn validate(q: &ViewportQuery) -> Vec<FieldError> {
+    let mut errors = Vec::new();
+    for (field, value) in [
+        ("minx", q.minx),
+        ("miny", q.miny),
+        ("maxx", q.maxx),
+        ("maxy", q.maxy),
+    ] {
+        if !value.is_finite() {
+            errors.push(FieldError::new(field, "must be a finite number"));
+        }
+    }
+    if q.minx >= q.maxx {
+        errors.push(FieldError::new("maxx", "must be greater than minx"));
+    }
+    if q.miny >= q.maxy {
+        errors.push(FieldError::new("maxy", "must be greater than miny"));
+    }
+    if !(q.zoom.is_finite() && q.zoom > 0.0) {
+        errors.push(FieldError::new("zoom", "must be a positive number"));
+    }
+    errors
+}
+
+pub fn is_detailed(zoom: f64) -> bool {
+    zoom >= DETAIL_ZOOM
+}
+
+/// World-space width of an aggregation cell, so cells stay the same size
+/// on screen whatever the zoom.
+pub fn cell_size(zoom: f64) -> f64 {
+    CELL_PIXELS / zoom
+}
+
+/// Fold per-difficulty rows into one centroid per cell.
+pub fn merge_cells(rows: Vec<CellRow>) -> Vec<GalaxyCentroid> {
+    struct Acc {
+        count: u64,
+        play_count: u64,
+        sum_x: f64,
+        sum_y: f64,
+        by_difficulty: BTreeMap<String, u64>,
+    }
+
+    let mut cells: BTreeMap<(i64, i64), Acc> = BTreeMap::new();
+    for row in rows {
+        let acc = cells.entry((row.cx, row.cy)).or_insert(Acc {
+            count: 0,
+            play_count: 0,
+            sum_x: 0.0,
+            sum_y: 0.0,
+            by_difficulty: BTreeMap::new(),
+        });
+        acc.count += row.count;
+        acc.play_count += row.play_count;
+        acc.sum_x += row.sum_x;
+        acc.sum_y += row.sum_y;
+        *acc.by_difficulty.entry(row.difficulty).or_default() += row.count;
+    }
+
+    cells
+        .into_values()
+        .filter(|acc| acc.count > 0)
+        .map(|acc| {
+            // Ties go to the alphabetically first difficulty
+            let difficulty = acc
+                .by_difficulty
+                .iter()
+                .fold(None::<(&String, u64)>, |best, (d, c)| match best {
+                    Some((_, bc)) if bc >= *c => best,
+                    _ => Some((d, *c)),
+                })
+                .map(|(d, _)| d.clone())
+                .unwrap_or_default();
+            GalaxyCentroid {
+                x: acc.sum_x / acc.count as f64,
+                y: acc.sum_y / acc.count as f64,
+                count: acc.count,
+                play_count: acc.play_count,
+                difficulty,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(cx: i64, cy: i64, difficulty: &str, count: u64, sum_x: f64, sum_y: f64) -> CellRow {
+        CellRow {
+            cx,
+            cy,
+            difficulty: difficulty.to_string(),
+            count,
+            play_count: count * 2,
+            sum_x,
+            sum_y,
+        }
+    }
+
+    #[test]
+    fn test_merge_cells() {
+        let cells = merge_cells(vec![
+            row(0, 0, "Easy", 2, 10.0, 20.0),
+            row(0, 0, "Hard", 3, 30.0, 30.0),
+            row(1, 0, "Easy", 1, 70.0, 5.0),
+            row(1, 0, "Medium", 1, 80.0, 5.0),
+        ]);
+        assert_eq!(cells.len(), 2);
+        assert_eq!((cells[0].count, cells[0].play_count), (5, 10));
+        assert_eq!((cells[0].x, cells[0].y), (8.0, 10.0));
+        assert_eq!(cells[0].difficulty, "Hard");
+        assert_eq!(cells[1].difficulty, "Easy");
+    }
+
+    #[test]
+    fn test_validate_viewport() {
+        let mut q = ViewportQuery {
+            minx: -100.0,
+            miny: -100.0,
+            maxx: 100.0,
+            maxy: 100.0,
+            zoom: 0.5,
+            limit: None,
+            state: None,
+            variant: None,
+        };
+        assert!(validate(&q).is_empty());
+        assert!(!is_detailed(q.zoom));
+        assert_eq!(cell_size(q.zoom), 128.0);
+
+        q.maxx = -200.0;
+        q.zoom = 0.0;
+        let fields: Vec<_> = validate(&q).iter().map(|e| e.field).collect();
+        assert_eq!(fields, vec!["maxx", "zoom"]);
+    }
+}
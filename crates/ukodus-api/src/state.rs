@@ -1,7 +1,10 @@
+use std::sync::Arc;
+
 use tokio::sync::broadcast;
 
 use crate::config::Config;
 use crate::graph::client::GraphClient;
+use crate::services::galaxy_cache::CacheCounters;
 
 /// JSON-serialized event broadcast to WebSocket clients.
 pub type GalaxyBroadcast = broadcast::Sender<String>;
@@ -12,4 +15,5 @@ pub struct AppState {
     pub redis: redis::aio::ConnectionManager,
     pub config: Config,
     pub galaxy_tx: GalaxyBroadcast,
+    pub galaxy_cache: Arc<CacheCounters>,
 }
@@ -0,0 +1,20 @@
+/// Difficulty tier reference data, easiest first: (name, min_se, max_se, color).
+///
+/// A puzzle belongs to the tier whose `[min_se, max_se)` range contains its
+/// max SE rating. Shared by the analyzer (which assigns tiers) and the API
+/// (which validates them).
+pub const DIFFICULTY_TIERS: &[(&str, f64, f64, &str)] = &[
+    ("Beginner", 1.0, 2.0, "#86efac"),
+    ("Easy", 2.0, 2.9, "#22c55e"),
+    ("Medium", 2.9, 3.5, "#f59e0b"),
+    ("Intermediate", 3.5, 4.5, "#fb923c"),
+    ("Hard", 4.5, 5.5, "#ef4444"),
+    ("Expert", 5.5, 6.5, "#dc2626"),
+    ("Master", 6.5, 8.0, "#9333ea"),
+    ("Extreme", 8.0, 11.0, "#1e293b"),
+];
+
+/// Position of a tier in [`DIFFICULTY_TIERS`], or `None` for an unknown name.
+pub fn tier_index(name: &str) -> Option<usize> {
+    DIFFICULTY_TIERS.iter().position(|(n, ..)| *n == name)
+}
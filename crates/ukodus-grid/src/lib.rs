@@ -0,0 +1,350 @@
+//! Parsing of 9x9 sudoku grids from the notations players and tools paste.
+//!
+//! Every accepted notation normalizes to the same canonical string:
+//!
+//! ```text
+//! 530070000600195000098000060...     81 digits, 0 for empty
+//! 53..7....6..195....98....6....     81 characters, . (or _) for empty
+//! 5 3 . | . 7 . | . . .              multi-line or whitespace-separated,
+//! ------+-------+------              with optional | + - , borders
+//! [[5,3,0,0,7,0,0,0,0], ...]         JSON 9x9 array, 0 or null for empty
+//! ```
+//!
+//! The canonical form is 81 characters `0`-`9` in row-major order, with `0`
+//! for an empty cell. It is what gets hashed, stored and handed to solvers.
+//!
+//! Parsing only checks the shape of the input; [`Grid::check_rules`] checks
+//! the givens against a [`Variant`]'s constraints.
+
+mod difficulty;
+mod variant;
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde_json::Value;
+
+pub use difficulty::{tier_index, DIFFICULTY_TIERS};
+pub use variant::Variant;
+
+pub const CELLS: usize = 81;
+
+/// A parsed grid: row-major cells, `0` for empty.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Grid([u8; CELLS]);
+
+/// Why a grid could not be parsed. Rows and columns are 1-based.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GridError {
+    Empty,
+    /// Wrong total number of cells in a text notation
+    CellCount(usize),
+    /// A row of a multi-line or JSON grid without exactly 9 cells
+    RowLength { row: usize, cells: usize },
+    /// A character that is neither a cell nor a separator
+    InvalidCharacter { ch: char, line: usize, column: usize },
+    /// JSON that is not a 9x9 array of digits
+    Json(String),
+    /// An empty cell where every cell must be filled
+    EmptyCell { row: usize, col: usize },
+    /// A solution digit that contradicts one of the puzzle's givens
+    GivenMismatch { row: usize, col: usize },
+    /// Two cells that see each other hold the same digit
+    Conflict { digit: u8, first: (usize, usize), second: (usize, usize) },
+}
+
+impl fmt::Display for GridError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GridError::Empty => write!(f, "is empty"),
+            GridError::CellCount(n) => write!(f, "must contain {CELLS} cells, found {n}"),
+            GridError::RowLength { row, cells } => {
+                write!(f, "row {row} has {cells} cells, expected 9")
+            }
+            GridError::InvalidCharacter { ch, line, column } => {
+                write!(f, "invalid character {ch:?} at line {line}, column {column}")
+            }
+            GridError::Json(msg) => write!(f, "invalid JSON grid: {msg}"),
+            GridError::EmptyCell { row, col } => write!(f, "cell r{row}c{col} is empty"),
+            GridError::GivenMismatch { row, col } => {
+                write!(f, "cell r{row}c{col} contradicts the puzzle's given")
+            }
+            GridError::Conflict { digit, first, second } => write!(
+                f,
+                "cells r{}c{} and r{}c{} both hold {digit}",
+                first.0, first.1, second.0, second.1
+            ),
+        }
+    }
+}
+
+impl std::error::Error for GridError {}
+
+impl Grid {
+    /// Parse a grid in any supported notation. Empty cells are allowed.
+    pub fn parse(input: &str) -> Result<Grid, GridError> {
+        let trimmed = input.trim();
+        if trimmed.is_empty() {
+            return Err(GridError::Empty);
+        }
+        if trimmed.starts_with('[') {
+            parse_json(trimmed)
+        } else {
+            parse_text(trimmed)
+        }
+    }
+
+    /// Parse a completed grid: like [`Grid::parse`], but every cell must be
+    /// filled.
+    pub fn parse_solution(input: &str) -> Result<Grid, GridError> {
+        let grid = Grid::parse(input)?;
+        match grid.0.iter().position(|&v| v == 0) {
+            Some(i) => Err(GridError::EmptyCell { row: i / 9 + 1, col: i % 9 + 1 }),
+            None => Ok(grid),
+        }
+    }
+
+    pub fn cells(&self) -> &[u8; CELLS] {
+        &self.0
+    }
+
+    /// The canonical 81-character string.
+    pub fn canonical(&self) -> String {
+        self.0.iter().map(|&v| char::from(b'0' + v)).collect()
+    }
+
+    /// Number of filled cells.
+    pub fn givens(&self) -> usize {
+        self.0.iter().filter(|&&v| v != 0).count()
+    }
+
+    /// Check that `solution` keeps every given of this puzzle.
+    pub fn check_solution(&self, solution: &Grid) -> Result<(), GridError> {
+        match (0..CELLS).find(|&i| self.0[i] != 0 && self.0[i] != solution.0[i]) {
+            Some(i) => Err(GridError::GivenMismatch { row: i / 9 + 1, col: i % 9 + 1 }),
+            None => Ok(()),
+        }
+    }
+
+    /// Check that no two filled cells that see each other under `variant`
+    /// hold the same digit. Reports the first conflict in row-major order.
+    pub fn check_rules(&self, variant: Variant) -> Result<(), GridError> {
+        for a in 0..CELLS {
+            let digit = self.0[a];
+            if digit == 0 {
+                continue;
+            }
+            if let Some(b) = (a + 1..CELLS).find(|&b| self.0[b] == digit && variant.sees(a, b)) {
+                return Err(GridError::Conflict {
+                    digit,
+                    first: (a / 9 + 1, a % 9 + 1),
+                    second: (b / 9 + 1, b % 9 + 1),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Grid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.canonical())
+    }
+}
+
+impl FromStr for Grid {
+    type Err = GridError;
+
+    fn from_str(s: &str) -> Result<Grid, GridError> {
+        Grid::parse(s)
+    }
+}
+
+fn parse_text(input: &str) -> Result<Grid, GridError> {
+    let mut cells: Vec<u8> = Vec::with_capacity(CELLS);
+    // Cells found on each line that had any, to report short rows in
+    // multi-line grids instead of a bare total
+    let mut row_lengths: Vec<usize> = Vec::new();
+
+    for (line_idx, line) in input.lines().enumerate() {
+        let before = cells.len();
+        for (col_idx, ch) in line.chars().enumerate() {
+            match ch {
+                '1'..='9' => cells.push(ch as u8 - b'0'),
+                '0' | '.' | '_' => cells.push(0),
+                '|' | '+' | '-' | ',' => {}
+                c if c.is_whitespace() => {}
+                _ => {
+                    return Err(GridError::InvalidCharacter {
+                        ch,
+                        line: line_idx + 1,
+                        column: col_idx + 1,
+                    })
+                }
+            }
+        }
+        if cells.len() > before {
+            row_lengths.push(cells.len() - before);
+        }
+    }
+
+    if row_lengths.len() == 9 {
+        if let Some(row) = row_lengths.iter().position(|&n| n != 9) {
+            return Err(GridError::RowLength { row: row + 1, cells: row_lengths[row] });
+        }
+    }
+    let cells: [u8; CELLS] = cells
+        .try_into()
+        .map_err(|cells: Vec<u8>| GridError::CellCount(cells.len()))?;
+    Ok(Grid(cells))
+}
+
+fn parse_json(input: &str) -> Result<Grid, GridError> {
+    let rows: Vec<Vec<Value>> =
+        serde_json::from_str(input).map_err(|e| GridError::Json(e.to_string()))?;
+    if rows.len() != 9 {
+        return Err(GridError::Json(format!("expected 9 rows, found {}", rows.len())));
+    }
+
+    let mut cells = [0u8; CELLS];
+    for (r, row) in rows.iter().enumerate() {
+        if row.len() != 9 {
+            return Err(GridError::RowLength { row: r + 1, cells: row.len() });
+        }
+        for (c, value) in row.iter().enumerate() {
+            let digit = match value {
+                Value::Null => Some(0),
+                Value::Number(n) => n.as_u64().filter(|d| *d <= 9).map(|d| d as u8),
+                Value::String(s) => match s.as_str() {
+                    "" | "." => Some(0),
+                    s => s.parse::<u8>().ok().filter(|d| *d <= 9),
+                },
+                _ => None,
+            };
+            cells[r * 9 + c] = digit.ok_or_else(|| {
+                GridError::Json(format!(
+                    "cell r{}c{} must be a digit 0-9 or null, found {value}",
+                    r + 1,
+                    c + 1
+                ))
+            })?;
+        }
+    }
+    Ok(Grid(cells))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ZEROED: &str =
+        "530070000600195000098000060800060003400803001700020006060000280000419005000080079";
+    const SOLUTION: &str =
+        "534678912672195348198342567859761423426853791713924856961537284287419635345286179";
+
+    #[test]
+    fn test_notations_normalize_to_canonical() {
+        let dotted = ZEROED.replace('0', ".");
+        let spaced: String = ZEROED.chars().map(|c| format!("{c} ")).collect();
+        let multi_line = "\
+            5 3 . | . 7 . | . . .\n\
+            6 . . | 1 9 5 | . . .\n\
+            . 9 8 | . . . | . 6 .\n\
+            ------+-------+------\n\
+            8 . . | . 6 . | . . 3\n\
+            4 . . | 8 . 3 | . . 1\n\
+            7 . . | . 2 . | . . 6\n\
+            ------+-------+------\n\
+            . 6 . | . . . | 2 8 .\n\
+            . . . | 4 1 9 | . . 5\n\
+            . . . | . 8 . | . 7 9\n";
+        let json: String = format!(
+            "[{}]",
+            ZEROED
+                .as_bytes()
+                .chunks(9)
+                .map(|row| {
+                    let digits: Vec<String> = row.iter().map(|b| (b - b'0').to_string()).collect();
+                    format!("[{}]", digits.join(","))
+                })
+                .collect::<Vec<_>>()
+                .join(",")
+        );
+
+        for input in [ZEROED, &dotted, &spaced, multi_line, &json] {
+            let grid = Grid::parse(input).unwrap_or_else(|e| panic!("{input:?}: {e}"));
+            assert_eq!(grid.canonical(), ZEROED);
+        }
+        assert_eq!(Grid::parse(ZEROED).unwrap().givens(), 30);
+    }
+
+    #[test]
+    fn test_json_accepts_null_and_strings() {
+        let mut rows = vec![vec![Value::from(0); 9]; 9];
+        rows[0][0] = Value::Null;
+        rows[0][1] = Value::from("7");
+        rows[0][2] = Value::from(".");
+        let grid = Grid::parse(&Value::from(rows).to_string()).unwrap();
+        assert_eq!(&grid.canonical()[..3], "070");
+    }
+
+    #[test]
+    fn test_errors_point_at_the_problem() {
+        assert_eq!(Grid::parse("  \n"), Err(GridError::Empty));
+        assert_eq!(Grid::parse(&ZEROED[..80]), Err(GridError::CellCount(80)));
+        assert_eq!(
+            Grid::parse(&format!("{}x", &ZEROED[..80])),
+            Err(GridError::InvalidCharacter { ch: 'x', line: 1, column: 81 })
+        );
+
+        let mut lines: Vec<&str> = ZEROED.as_bytes().chunks(9).map(|r| std::str::from_utf8(r).unwrap()).collect();
+        lines[4] = "80006000";
+        lines[5] = "34008030010";
+        assert_eq!(
+            Grid::parse(&lines.join("\n")),
+            Err(GridError::RowLength { row: 5, cells: 8 })
+        );
+
+        assert!(matches!(Grid::parse("[[1,2,3]]"), Err(GridError::Json(_))));
+        assert!(matches!(Grid::parse("[[1,2,3"), Err(GridError::Json(_))));
+    }
+
+    #[test]
+    fn test_check_rules_per_variant() {
+        let puzzle = Grid::parse(ZEROED).unwrap();
+        assert_eq!(puzzle.check_rules(Variant::Classic), Ok(()));
+        // The sample solution repeats digits on its diagonals
+        let solution = Grid::parse(SOLUTION).unwrap();
+        assert_eq!(solution.check_rules(Variant::Classic), Ok(()));
+        assert!(matches!(
+            solution.check_rules(Variant::Diagonal),
+            Err(GridError::Conflict { .. })
+        ));
+
+        let mut duplicate = ZEROED.to_string();
+        duplicate.replace_range(2..3, "5");
+        assert_eq!(
+            Grid::parse(&duplicate).unwrap().check_rules(Variant::Classic),
+            Err(GridError::Conflict { digit: 5, first: (1, 1), second: (1, 3) })
+        );
+    }
+
+    #[test]
+    fn test_solution_must_be_complete_and_match_givens() {
+        let puzzle = Grid::parse(ZEROED).unwrap();
+        let solution = Grid::parse_solution(SOLUTION).unwrap();
+        assert_eq!(puzzle.check_solution(&solution), Ok(()));
+
+        assert_eq!(
+            Grid::parse_solution(ZEROED),
+            Err(GridError::EmptyCell { row: 1, col: 3 })
+        );
+        let mut wrong = SOLUTION.to_string();
+        wrong.replace_range(0..2, "35");
+        let wrong = Grid::parse_solution(&wrong).unwrap();
+        assert_eq!(
+            puzzle.check_solution(&wrong),
+            Err(GridError::GivenMismatch { row: 1, col: 1 })
+        );
+    }
+}
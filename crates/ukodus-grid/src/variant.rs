@@ -0,0 +1,77 @@
+use serde::{Deserialize, Serialize};
+
+/// Rule set a puzzle is played under.
+///
+/// Every variant keeps the classic row, column and box constraints and may
+/// add its own. Stored as `p.variant` / `r.variant`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Variant {
+    #[default]
+    Classic,
+    /// X-Sudoku: both main diagonals must also hold 1-9 once each
+    Diagonal,
+}
+
+impl Variant {
+    pub const ALL: &'static [Variant] = &[Variant::Classic, Variant::Diagonal];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Variant::Classic => "classic",
+            Variant::Diagonal => "diagonal",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Variant> {
+        Variant::ALL.iter().copied().find(|v| v.as_str() == s)
+    }
+
+    /// Whether cells `a` and `b` (row-major indices) must hold different
+    /// digits under this variant.
+    pub fn sees(self, a: usize, b: usize) -> bool {
+        if a == b {
+            return false;
+        }
+        let (ra, ca, rb, cb) = (a / 9, a % 9, b / 9, b % 9);
+        if ra == rb || ca == cb || (ra / 3 == rb / 3 && ca / 3 == cb / 3) {
+            return true;
+        }
+        match self {
+            Variant::Classic => false,
+            Variant::Diagonal => (ra == ca && rb == cb) || (ra + ca == 8 && rb + cb == 8),
+        }
+    }
+
+    /// Whether `digit` can go in `idx` without repeating it in any unit the
+    /// cell belongs to.
+    pub fn allows(self, board: &[u8; 81], idx: usize, digit: u8) -> bool {
+        (0..81).all(|i| board[i] != digit || !self.sees(idx, i))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diagonal_adds_constraints() {
+        // r1c1 and r9c9 share only the main diagonal
+        assert!(!Variant::Classic.sees(0, 80));
+        assert!(Variant::Diagonal.sees(0, 80));
+        // r1c9 and r9c1 share the anti-diagonal
+        assert!(Variant::Diagonal.sees(8, 72));
+        // r1c1 and r9c1 share a column under both rule sets
+        assert!(Variant::Classic.sees(0, 72) && Variant::Diagonal.sees(0, 72));
+        // r2c1 is on neither diagonal
+        assert!(!Variant::Diagonal.sees(9, 80));
+
+        let mut board = [0u8; 81];
+        board[40] = 5; // centre cell, on both diagonals
+        assert!(Variant::Classic.allows(&board, 0, 5));
+        assert!(!Variant::Diagonal.allows(&board, 0, 5));
+        assert!(!Variant::Diagonal.allows(&board, 72, 5));
+        assert!(Variant::Diagonal.allows(&board, 1, 5));
+        assert_eq!(serde_json::to_string(&Variant::Diagonal).unwrap(), "\"diagonal\"");
+    }
+}
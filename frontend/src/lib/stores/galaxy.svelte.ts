@@ -153,6 +153,8 @@ class GalaxyStore {
 	activeFilters = $state<Set<string>>(new Set());
 	selectedNode = $state<GalaxyNode | null>(null);
 	loading = $state(true);
+	/** The overview is fetched with the API's default variant */
+	readonly variant = 'classic';
 	private sse: EventSource | null = null;
 
 	constructor() {
@@ -240,6 +242,9 @@ class GalaxyStore {
 			this.sse.onmessage = (event) => {
 				try {
 					const msg = JSON.parse(event.data);
+					// Each variant is its own galaxy: skip puzzles from the others
+					const isNode = msg.type === 'new_puzzle' || msg.type === 'puzzle_discovered';
+					if (isNode && (msg.data?.variant ?? 'classic') !== this.variant) return;
 					if (msg.type === 'new_puzzle' && msg.data) {
 						const newNode: GalaxyNode = {
 							id: msg.data.puzzle_hash || msg.data.id,
@@ -254,6 +259,18 @@ class GalaxyStore {
 							avg_time_secs: msg.data.avg_time_secs
 						};
 						this.addLiveNode(newNode, msg.data.edges);
+					} else if (msg.type === 'puzzle_discovered' && msg.data) {
+						// Mined puzzles only join the galaxy once someone plays them
+						this.addLiveNode({
+							id: msg.data.puzzle_hash,
+							puzzle_hash: msg.data.puzzle_hash,
+							short_code: msg.data.short_code,
+							difficulty: msg.data.difficulty,
+							se_rating: msg.data.se_rating,
+							play_count: 1,
+							max_technique: null,
+							techniques: []
+						});
 					} else if (msg.type === 'play_result' && msg.data) {
 						this.updateNodePlayCount(
 							msg.data.puzzle_hash,
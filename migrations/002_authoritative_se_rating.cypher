@@ -0,0 +1,21 @@
+// Migration: Make analyzer-computed SE ratings authoritative
+// Run once via Neo4j Browser after deploying the re-rating analyzer.
+//
+// Puzzles analyzed before this change still carry the client-reported
+// se_rating/difficulty. Keep those as reported_* and replace them with the
+// analyzer's max_se_rating and IN_TIER tier. Discrepancies above 0.5 (the
+// analyzer's default threshold) are recorded in rating_discrepancy.
+
+MATCH (p:Puzzle)
+WHERE p.max_se_rating IS NOT NULL AND p.reported_se_rating IS NULL
+OPTIONAL MATCH (p)-[:IN_TIER]->(d:DifficultyTier)
+WITH p, d.name AS tier, p.se_rating AS reported_se, p.difficulty AS reported_diff
+SET p.reported_se_rating = reported_se,
+    p.reported_difficulty = reported_diff,
+    p.se_rating = p.max_se_rating,
+    p.difficulty = COALESCE(tier, reported_diff),
+    p.rating_discrepancy = CASE
+        WHEN reported_se IS NOT NULL AND abs(reported_se - p.max_se_rating) > 0.5
+        THEN abs(reported_se - p.max_se_rating)
+        ELSE null END,
+    p.rated_at = datetime();
@@ -0,0 +1,29 @@
+// Migration: Server-allocated short codes
+// Run once via Neo4j Browser before deploying server-side short codes.
+//
+// short_code used to hold the client's 8-character WASM code (or '' when
+// absent). Move those values to client_code so short_code only ever holds
+// 6-character server codes, then enforce uniqueness. Server codes are never
+// 8 characters, so re-running this is harmless.
+
+MATCH (p:Puzzle)
+WHERE p.short_code IS NOT NULL AND size(p.short_code) <> 6
+SET p.client_code = CASE WHEN p.short_code = '' THEN p.client_code
+                         ELSE COALESCE(p.client_code, p.short_code) END
+REMOVE p.short_code;
+
+MATCH (s:Share)
+WHERE s.short_code IS NOT NULL AND size(s.short_code) <> 6
+SET s.client_code = CASE WHEN s.short_code = '' THEN s.client_code
+                         ELSE COALESCE(s.client_code, s.short_code) END
+REMOVE s.short_code;
+
+CREATE CONSTRAINT puzzle_short_code IF NOT EXISTS
+FOR (p:Puzzle) REQUIRE p.short_code IS UNIQUE;
+
+CREATE CONSTRAINT share_short_code IF NOT EXISTS
+FOR (s:Share) REQUIRE s.short_code IS UNIQUE;
+
+// Then allocate deterministic codes for existing nodes by calling
+//   POST /api/v1/internal/short-codes/backfill   (X-Api-Key)
+// until it reports {"puzzles": 0, "shares": 0}.
@@ -0,0 +1,9 @@
+// Migration: Puzzle updated_at for Last-Modified
+// Run once via Neo4j Browser. Safe to re-run.
+//
+// Puzzle reads now send Last-Modified from p.updated_at (falling back to
+// created_at). Seed it from the most recent timestamp we already track.
+
+MATCH (p:Puzzle)
+WHERE p.updated_at IS NULL
+SET p.updated_at = COALESCE(p.empirical_updated_at, p.rated_at, p.retired_at, p.created_at, datetime());
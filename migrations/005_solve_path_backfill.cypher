@@ -0,0 +1,10 @@
+// Migration: Backfill ordered solving paths
+// Run once via Neo4j Browser after deploying the analyzer that writes
+// p.solve_path, then run `ukodus-analyzer analyze-batch`.
+//
+// Puzzles analyzed before this change only have per-technique counts.
+// Queue them for re-analysis; edges are MERGEd, so nothing is duplicated.
+
+MATCH (p:Puzzle)
+WHERE p.max_se_rating IS NOT NULL AND p.solve_path IS NULL
+SET p.needs_analysis = true;
@@ -0,0 +1,23 @@
+// Migration: Solve-time distribution per puzzle
+// Run once via Neo4j Browser. Safe to re-run.
+//
+// The API now keeps p.solve_time_hist / p.solve_time_p10..p90 up to date
+// on every result. Backfill them for puzzles that have not been played
+// since. Edges must match solve_times::BUCKET_EDGES_SECS.
+
+:param edges => [30, 60, 90, 120, 180, 240, 300, 420, 600, 900, 1200, 1800, 2700, 3600];
+
+MATCH (p:Puzzle)
+OPTIONAL MATCH (r:GameResult)-[:FOR_PUZZLE]->(p)
+WHERE r.result = 'Win' AND r.verified = true
+WITH p, collect(r.time_secs) AS times,
+     percentileCont(r.time_secs, 0.1) AS p10,
+     percentileCont(r.time_secs, 0.5) AS p50,
+     percentileCont(r.time_secs, 0.9) AS p90
+SET p.verified_win_count = size(times),
+    p.solve_time_p10 = p10,
+    p.solve_time_p50 = p50,
+    p.solve_time_p90 = p90,
+    p.solve_time_hist = [i IN range(0, size($edges)) |
+        size([t IN times WHERE (i = 0 OR t >= $edges[i - 1])
+                           AND (i = size($edges) OR t < $edges[i])])];
@@ -0,0 +1,13 @@
+// Migration: DISCOVERED credit for already-discovered mined puzzles
+// Run once via Neo4j Browser. Safe to re-run.
+//
+// Puzzles discovered before the DISCOVERED relationship existed are
+// credited to their earliest game result.
+
+MATCH (p:Puzzle {mined: true, discovered: true})
+WHERE NOT ()-[:DISCOVERED]->(p)
+MATCH (r:GameResult)-[:FOR_PUZZLE]->(p)
+WITH p, r ORDER BY r.created_at ASC
+WITH p, head(collect(r)) AS first
+SET p.discovered_at = COALESCE(p.discovered_at, first.created_at)
+CREATE (first)-[:DISCOVERED {discovered_at: first.created_at}]->(p);
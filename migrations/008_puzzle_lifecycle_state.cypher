@@ -0,0 +1,21 @@
+// Migration: explicit lifecycle state on puzzles
+// Run once via Neo4j Browser. Safe to re-run.
+//
+// Replaces the mined/discovered/needs_analysis/analysis_error/retired flags
+// with p.source ('mined' | 'player') and p.state.
+
+MATCH (p:Puzzle)
+WHERE p.state IS NULL
+SET p.source = CASE WHEN p.mined = true THEN 'mined' ELSE 'player' END,
+    p.state = CASE
+      WHEN p.retired = true THEN 'retired'
+      WHEN p.mined = true AND COALESCE(p.discovered, false) = false THEN 'mined'
+      WHEN p.analysis_error = true THEN 'failed'
+      WHEN p.needs_analysis = true THEN 'queued'
+      WHEN p.max_se_rating IS NOT NULL THEN 'analyzed'
+      ELSE 'queued'
+    END,
+    p.state_changed_at = datetime()
+REMOVE p.mined, p.discovered, p.needs_analysis, p.analysis_error, p.retired;
+
+CREATE INDEX puzzle_state IF NOT EXISTS FOR (p:Puzzle) ON (p.state);
@@ -0,0 +1,15 @@
+// Migration: rule-set variant on puzzles, results and shares
+// Run once via Neo4j Browser. Safe to re-run.
+//
+// Everything created before variants existed is classic sudoku.
+
+MATCH (p:Puzzle) WHERE p.variant IS NULL
+SET p.variant = 'classic';
+
+MATCH (r:GameResult) WHERE r.variant IS NULL
+SET r.variant = 'classic';
+
+MATCH (s:Share) WHERE s.variant IS NULL
+SET s.variant = 'classic';
+
+CREATE INDEX puzzle_variant IF NOT EXISTS FOR (p:Puzzle) ON (p.variant);
@@ -0,0 +1,10 @@
+// Migration: point property and index for galaxy viewport queries
+// Run once via Neo4j Browser. Safe to re-run.
+//
+// `ukodus-analyzer layout` writes p.location alongside p.x/p.y; this
+// backfills puzzles laid out before it did.
+
+MATCH (p:Puzzle) WHERE p.x IS NOT NULL AND p.y IS NOT NULL AND p.location IS NULL
+SET p.location = point({x: p.x, y: p.y});
+
+CREATE POINT INDEX puzzle_location IF NOT EXISTS FOR (p:Puzzle) ON (p.location);
@@ -0,0 +1,9 @@
+// Migration: Cluster nodes for detected puzzle communities
+// Run once via Neo4j Browser. Safe to re-run.
+//
+// Clusters are written by `ukodus-analyzer communities`; this only adds the
+// constraint and index its queries rely on.
+
+CREATE CONSTRAINT cluster_id IF NOT EXISTS FOR (c:Cluster) REQUIRE c.id IS UNIQUE;
+
+CREATE INDEX cluster_variant IF NOT EXISTS FOR (c:Cluster) ON (c.variant);
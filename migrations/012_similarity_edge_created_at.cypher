@@ -0,0 +1,17 @@
+// Migration: created_at on SHARES_TECHNIQUE_PROFILE edges
+// Run once via Neo4j Browser. Safe to re-run.
+//
+// `/galaxy/overview?as_of=` and `/galaxy/timeline` replay the galaxy's
+// growth from edge creation times, which the analyzer now sets. Edges made
+// before that get the time their later endpoint appeared, the earliest the
+// analyzer could have linked them.
+
+MATCH (a:Puzzle)-[s:SHARES_TECHNIQUE_PROFILE]->(b:Puzzle)
+WHERE s.created_at IS NULL
+WITH s,
+     COALESCE(a.discovered_at, a.created_at, datetime()) AS ta,
+     COALESCE(b.discovered_at, b.created_at, datetime()) AS tb
+SET s.created_at = CASE WHEN ta > tb THEN ta ELSE tb END;
+
+CREATE INDEX shares_created_at IF NOT EXISTS
+FOR ()-[s:SHARES_TECHNIQUE_PROFILE]-() ON (s.created_at);
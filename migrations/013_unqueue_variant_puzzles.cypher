@@ -0,0 +1,9 @@
+// Migration: move variant puzzles out of the analysis queue
+// Run once via Neo4j Browser. Safe to re-run.
+//
+// The analyzer only rates classic puzzles, so diagonal puzzles queued by
+// earlier API versions would wait forever. Only classic puzzles are queued
+// now; return the rest to discovered.
+
+MATCH (p:Puzzle {state: 'queued'}) WHERE p.variant <> 'classic'
+SET p.state = 'discovered', p.state_changed_at = datetime(), p.updated_at = datetime();
@@ -0,0 +1,22 @@
+// Migration: canonical puzzle strings
+// Run once via Neo4j Browser before 015. Safe to re-run.
+//
+// Mined puzzles used to be stored as submitted, with `.` for empty cells.
+// The server now finds existing puzzles by canonical grid and variant, so
+// rewrite those to the 0-form. A rewrite can reveal two nodes for the same
+// grid: the oldest keeps serving (it is the one the lookup picks) and the
+// rest are flagged with `duplicate_of` for a manual merge.
+
+MATCH (p:Puzzle) WHERE p.puzzle_string CONTAINS '.'
+SET p.puzzle_string = replace(p.puzzle_string, '.', '0');
+
+MATCH (p:Puzzle)
+WITH p ORDER BY p.created_at ASC
+WITH p.puzzle_string AS ps, p.variant AS variant, collect(p) AS nodes
+WHERE size(nodes) > 1
+UNWIND tail(nodes) AS dup
+SET dup.duplicate_of = head(nodes).hash;
+
+// List what needs merging:
+//   MATCH (p:Puzzle) WHERE p.duplicate_of IS NOT NULL
+//   RETURN p.duplicate_of, collect(p.hash);
@@ -0,0 +1,7 @@
+// Migration: index puzzles by grid and variant
+// Run once via Neo4j Browser. Safe to re-run.
+//
+// Submitted puzzles are matched to existing ones by canonical grid and
+// variant instead of by a client-computed hash, so the lookup needs an index.
+
+CREATE INDEX puzzle_string_variant IF NOT EXISTS FOR (p:Puzzle) ON (p.puzzle_string, p.variant);
@@ -0,0 +1,24 @@
+// Migration: one short-code namespace for puzzles and shares
+// Run once via Neo4j Browser before deploying. Safe to re-run.
+//
+// Codes used to be unique per label, so a puzzle and a share could hold the
+// same code. Each code is now claimed by a :ShortCode node naming its owner.
+// Shares claim first because /s/{code} already resolved them first; a puzzle
+// whose code a share holds loses it and gets a new one from the backfill.
+
+CREATE CONSTRAINT short_code_unique IF NOT EXISTS
+FOR (c:ShortCode) REQUIRE c.code IS UNIQUE;
+
+MATCH (s:Share) WHERE s.short_code IS NOT NULL
+MERGE (c:ShortCode {code: s.short_code})
+ON CREATE SET c.label = 'Share', c.key = s.share_id;
+
+MATCH (p:Puzzle) WHERE p.short_code IS NOT NULL
+MERGE (c:ShortCode {code: p.short_code})
+ON CREATE SET c.label = 'Puzzle', c.key = p.hash
+WITH p, c
+WHERE c.label <> 'Puzzle' OR c.key <> p.hash
+REMOVE p.short_code;
+
+// Then re-run POST /api/v1/internal/short-codes/backfill (X-Api-Key) until
+// it reports {"puzzles": 0, "shares": 0}.